@@ -0,0 +1,280 @@
+// Headless benchmark, plays seeded games of given settings with the built-in logic player
+use minesweeper6d::config::{self, Config};
+use minesweeper6d::minesweeper_model::{CellState, DIMENSIONS_COUNT, GameBoard, GameState, InitialGameSettings};
+use minesweeper6d::solver::BoardAnalysis;
+
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+const USAGE: &str = "Usage: minesweeper6d-bench [OPTIONS]
+
+Plays seeded games with the built-in logic player. The player probes every cell that is known
+to be safe and guesses the cell least likely to hold a mine when there is none.
+
+  --config PATH    configuration file with additional presets (default: config.toml, if present,
+                   otherwise minesweeper6d/config.toml in the user's config directory)
+  --preset NAME    preset to benchmark, name or index (may be repeated, default: all presets)
+  --size X,Y,..    ad-hoc board dimensions instead of a preset, e.g. 4,4,4,4,1,1
+  --wrap X,Y,..    wrapping of the ad-hoc board, e.g. 0,0,0,1,0,0
+  --mines N        number of mines of the ad-hoc board
+  --games N        number of games per setting (default: 100)
+  --seed HEX       seed of the first game, the following games use derived seeds (default: 0)
+  --format FORMAT  `csv` or `json` (default: csv)";
+
+// Seeds of consecutive games are spread out, so runs with nearby base seeds don't share boards
+const SEED_STEP: u64 = 0x9E37_79B9_7F4A_7C15;
+
+#[derive(PartialEq)]
+enum Format {
+    Csv,
+    Json,
+}
+
+struct Options {
+    settings: Vec<InitialGameSettings>,
+    games: u32,
+    seed: u64,
+    format: Format,
+}
+
+// Outcome of a single game played by the logic player
+struct GameResult {
+    victory: bool,
+    guesses: u32,
+    generation_time: Duration,
+    solve_time: Duration,
+    bbbv: u32,
+}
+
+#[derive(Serialize)]
+struct Report {
+    name: String,
+    size: [usize; DIMENSIONS_COUNT],
+    wrap: [bool; DIMENSIONS_COUNT],
+    mines: u32,
+    games: u32,
+    won: u32,
+    won_without_guessing: u32,
+    win_rate: f64,
+    no_guess_win_rate: f64,
+    average_guesses: f64,
+    average_generation_ms: f64,
+    average_solve_ms: f64,
+    bbbv_average: f64,
+    bbbv_min: u32,
+    bbbv_max: u32,
+    bbbv_deviation: f64,
+}
+
+impl Report {
+    fn new(settings: &InitialGameSettings, results: &[GameResult]) -> Self {
+        let games = results.len() as u32;
+        let count = results.len().max(1) as f64;
+        let won = results.iter().filter(|r| r.victory).count() as u32;
+        let won_without_guessing = results.iter().filter(|r| r.victory && r.guesses == 0).count() as u32;
+        let bbbv_average = results.iter().map(|r| r.bbbv as f64).sum::<f64>() / count;
+        let bbbv_variance = results.iter().map(|r| (r.bbbv as f64 - bbbv_average).powi(2)).sum::<f64>() / count;
+        Self {
+            name: settings.name.clone(),
+            size: settings.size,
+            wrap: settings.wrap,
+            mines: settings.mines,
+            games,
+            won,
+            won_without_guessing,
+            win_rate: won as f64 / count,
+            no_guess_win_rate: won_without_guessing as f64 / count,
+            average_guesses: results.iter().map(|r| r.guesses as f64).sum::<f64>() / count,
+            average_generation_ms: results.iter().map(|r| r.generation_time.as_secs_f64()).sum::<f64>() * 1000.0 / count,
+            average_solve_ms: results.iter().map(|r| r.solve_time.as_secs_f64()).sum::<f64>() * 1000.0 / count,
+            bbbv_average,
+            bbbv_min: results.iter().map(|r| r.bbbv).min().unwrap_or(0),
+            bbbv_max: results.iter().map(|r| r.bbbv).max().unwrap_or(0),
+            bbbv_deviation: bbbv_variance.sqrt(),
+        }
+    }
+
+    const CSV_HEADER: &'static str = "name,size,wrap,mines,games,won,won_without_guessing,win_rate,no_guess_win_rate,\
+average_guesses,average_generation_ms,average_solve_ms,bbbv_average,bbbv_min,bbbv_max,bbbv_deviation";
+
+    fn csv_row(&self) -> String {
+        let settings = InitialGameSettings { size: self.size, wrap: self.wrap, ..Default::default() };
+        format!("\"{}\",{},{},{},{},{},{},{:.4},{:.4},{:.3},{:.3},{:.3},{:.2},{},{},{:.2}",
+                self.name.replace('"', "\"\""),
+                settings.size_description().replace(' ', ""),
+                self.wrap.iter().map(|&w| if w {"1"} else {"0"}).collect::<Vec<_>>().join(""),
+                self.mines, self.games, self.won, self.won_without_guessing,
+                self.win_rate, self.no_guess_win_rate, self.average_guesses,
+                self.average_generation_ms, self.average_solve_ms,
+                self.bbbv_average, self.bbbv_min, self.bbbv_max, self.bbbv_deviation)
+    }
+}
+
+// Every game starts in the middle of the board. Seeds are tried one after another
+// until the starting cell is empty, so the same base seed always gives the same boards.
+fn generate(settings: &InitialGameSettings, seed: u64) -> (GameBoard, [usize; DIMENSIONS_COUNT]) {
+    let start = settings.size.map(|s| s / 2);
+    let mut seed = seed;
+    loop {
+        let game = GameBoard::new(settings.size, settings.wrap, settings.mines, None, Some(seed));
+        if let CellState::UndiscoveredEmpty(..) = game.cell_at(start) {
+            return (game, start);
+        }
+        seed = seed.wrapping_add(1);
+    }
+}
+
+fn play(settings: &InitialGameSettings, seed: u64) -> GameResult {
+    let generation_start = Instant::now();
+    let (mut game, start) = generate(settings, seed);
+    let generation_time = generation_start.elapsed();
+
+    let solve_start = Instant::now();
+    let mut guesses = 0;
+    game.probe_at(start, false);
+    while game.state() == GameState::Running {
+        let analysis = BoardAnalysis::new(&game);
+        if analysis.safe_cells().is_empty() {
+            // Nothing is certain, probe the least dangerous cell
+            let guess = game.all_coords()
+                .filter_map(|c| analysis.mine_probability(c).map(|p| (c, p)))
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+                .map(|(c, _)| c)
+                .expect("running game has an undiscovered cell");
+            guesses += 1;
+            game.probe_at(guess, false);
+        } else {
+            for &coords in analysis.safe_cells() {
+                if game.probe_at(coords, false) != GameState::Running {
+                    break;
+                }
+            }
+        }
+    }
+
+    GameResult {
+        victory: game.state() == GameState::Victory,
+        guesses,
+        generation_time,
+        solve_time: solve_start.elapsed(),
+        bbbv: game.bbbv(),
+    }
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut config_path = None;
+    let mut preset_names = Vec::new();
+    let mut custom: Option<InitialGameSettings> = None;
+    let mut options = Options { settings: vec![], games: 100, seed: 0, format: Format::Csv };
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--help" || arg == "-h" {
+            return Err(USAGE.into());
+        }
+        let value = args.next().ok_or_else(|| format!("Missing value for `{}`\n\n{}", arg, USAGE))?;
+        let custom_settings = || InitialGameSettings { name: "Custom".into(), ..Default::default() };
+        match arg.as_str() {
+            "--config" => config_path = Some(value),
+            "--preset" => preset_names.push(value),
+            "--size" => {
+                custom.get_or_insert_with(custom_settings).size = config::parse_size(&value)
+                    .ok_or_else(|| format!("Invalid value of `--size`: {}", value))?;
+            },
+            "--wrap" => {
+                custom.get_or_insert_with(custom_settings).wrap = config::parse_wrap(&value)
+                    .ok_or_else(|| format!("Invalid value of `--wrap`: {}", value))?;
+            },
+            "--mines" => {
+                custom.get_or_insert_with(custom_settings).mines = value.parse()
+                    .map_err(|_| format!("Invalid value of `--mines`: {}", value))?;
+            },
+            "--games" => {
+                options.games = value.parse().map_err(|_| format!("Invalid value of `--games`: {}", value))?;
+            },
+            "--seed" => {
+                options.seed = u64::from_str_radix(&value, 16)
+                    .map_err(|_| format!("Invalid value of `--seed`: {}", value))?;
+            },
+            "--format" => {
+                options.format = match value.as_str() {
+                    "csv" => Format::Csv,
+                    "json" => Format::Json,
+                    _ => return Err(format!("Invalid value of `--format`: {}", value)),
+                };
+            },
+            _ => return Err(format!("Unknown argument `{}`\n\n{}", arg, USAGE)),
+        }
+    }
+
+    if custom.is_none() || !preset_names.is_empty() {
+        // Built-in presets, with the ones from the configuration file layered over them
+        // Same lookup as the game, only a file given with `--config` has to exist
+        let given = config_path.is_some();
+        let config_path = config::config_file_path(config_path.map(PathBuf::from));
+        let config_text = match &config_path {
+            Some(path) if given || path.is_file() => fs::read_to_string(path)
+                .map_err(|e| format!("Unable to read `{}`: {}", path.display(), e))?,
+            _ => "".into(),
+        };
+        let (config, problems) = Config::layered(&config_text);
+        for problem in problems {
+            eprintln!("{}: {}", config_path.as_ref().map(|p| p.display().to_string()).unwrap_or_default(), problem);
+        }
+        let presets = config.presets;
+
+        if preset_names.is_empty() && custom.is_none() {
+            options.settings = presets;
+        } else {
+            for name in &preset_names {
+                let preset = presets.iter().find(|p| &p.name == name)
+                    .or_else(|| name.parse::<usize>().ok().and_then(|i| presets.get(i)))
+                    .ok_or_else(|| format!("Unknown preset `{}`", name))?;
+                options.settings.push(preset.clone());
+            }
+        }
+    }
+    if let Some(settings) = custom {
+        options.settings.push(settings);
+    }
+
+    for settings in &options.settings {
+        let total: usize = settings.size.iter().product();
+        if settings.mines == 0 || settings.mines as usize >= total {
+            return Err(format!("`{}`: number of mines has to be between 1 and {}", settings.name, total - 1));
+        }
+    }
+    Ok(options)
+}
+
+fn main() {
+    let options = match parse_args() {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}", message);
+            std::process::exit(2);
+        }
+    };
+
+    if options.format == Format::Csv {
+        println!("{}", Report::CSV_HEADER);
+    }
+    let mut reports = Vec::new();
+    for settings in &options.settings {
+        let results: Vec<_> = (0..options.games as u64)
+            .map(|ii| play(settings, options.seed.wrapping_add(ii.wrapping_mul(SEED_STEP))))
+            .collect();
+        let report = Report::new(settings, &results);
+        // Print as soon as possible, big presets may take a while
+        if options.format == Format::Csv {
+            println!("{}", report.csv_row());
+        }
+        reports.push(report);
+    }
+    if options.format == Format::Json {
+        println!("{}", serde_json::to_string_pretty(&reports).expect("report is serializable"));
+    }
+}
@@ -0,0 +1,22 @@
+// Headless game server for external players, see `minesweeper6d::protocol` for the protocol
+use minesweeper6d::protocol::Server;
+
+use std::io::{self, BufRead, Write};
+
+fn main() -> io::Result<()> {
+    let mut server = Server::new();
+    let mut stdout = io::stdout().lock();
+    for line in io::stdin().lock().lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = server.handle_line(&line);
+        writeln!(stdout, "{}", serde_json::to_string(&response).expect("response is serializable"))?;
+        stdout.flush()?;
+        if server.should_quit() {
+            break;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,409 @@
+// Terminal front-end, plays the same game model as the egui version
+
+use minesweeper6d::config;
+use minesweeper6d::hhmmss::Hhmmss;
+use minesweeper6d::minesweeper_model::{CellState, DIMENSIONS_COUNT, GameBoard, GameState, InitialGameSettings};
+
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+use crossterm::{cursor, execute, queue, terminal};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::style::{Attribute, Color, Print, ResetColor, SetAttribute, SetBackgroundColor, SetForegroundColor};
+
+// Characters per cell and spacing between blocks, mirrors the nesting of the egui board:
+//   x, z, v go across, y, u, w go down
+const CELL_WIDTH: usize = 3;
+const GAP_Z: usize = 1;
+const GAP_V: usize = 3;
+const GAP_U: usize = 1;
+const GAP_W: usize = 2;
+// Lines reserved for the status bar and help
+const STATUS_LINES: usize = 3;
+
+const HIGHLIGHT_COLORS: [Color; 8] = [Color::Yellow, Color::DarkYellow, Color::Green, Color::White,
+                                      Color::Magenta, Color::DarkBlue, Color::DarkGreen, Color::DarkMagenta];
+
+const USAGE: &str = "Usage: minesweeper6d-tui [--size X,Y,Z,U,V,W] [--wrap X,Y,Z,U,V,W] [--mines N] [--seed HEX]
+
+  --size   dimensions of the board, e.g. 4,4,4,4,1,1
+  --wrap   which dimensions wrap around, e.g. 0,0,0,1,0,0
+  --mines  number of mines
+  --seed   hexadecimal seed, the board is generated on the first probe if omitted";
+
+const HELP: &str = "move x: a/d ←/→  y: w/s ↑/↓  z: j/l  u: i/k  v: J/L  w: I/K | probe: space  mark: f  chord: c  \
+highlight: h (group 1-8) | Δ: t  new: n  quit: q";
+
+struct TerminalGuard;
+
+impl TerminalGuard {
+    fn new() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        execute!(io::stdout(), terminal::EnterAlternateScreen, cursor::Hide)?;
+        Ok(Self)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), ResetColor, cursor::Show, terminal::LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+struct MinesweeperTerminal {
+    settings: InitialGameSettings,
+    game: Option<GameBoard>,
+    start_time: Option<Instant>,
+    end_time: Option<Instant>,
+
+    cursor: [usize; DIMENSIONS_COUNT],
+    highlight_group: usize,
+    show_delta: bool,
+
+    // Top-left corner of the visible part of the board (in characters)
+    scroll: (usize, usize),
+}
+
+impl MinesweeperTerminal {
+    fn new(settings: InitialGameSettings) -> Self {
+        Self {
+            settings,
+            game: None,
+            start_time: None,
+            end_time: None,
+
+            cursor: [0; DIMENSIONS_COUNT],
+            highlight_group: 0,
+            show_delta: true,
+
+            scroll: (0, 0),
+        }
+    }
+
+    fn reset(&mut self) {
+        self.game = None;
+        self.start_time = None;
+        self.end_time = None;
+    }
+
+    fn start(&mut self) {
+        let settings = &self.settings;
+        self.start_time = Some(Instant::now());
+        self.end_time = None;
+        if let Some(seed) = &settings.seed {
+            let mut game = GameBoard::new(settings.size, settings.wrap, settings.mines, None,
+                                          u64::from_str_radix(seed, 16).ok());
+            game.probe_at(self.cursor, true);
+            self.game = Some(game);
+        } else {
+            self.game = Some(GameBoard::new(settings.size, settings.wrap, settings.mines, Some(self.cursor), None));
+        }
+        self.check_end();
+    }
+
+    fn check_end(&mut self) {
+        if let Some(game) = &self.game {
+            if game.state() != GameState::Running && self.end_time.is_none() {
+                self.end_time = Some(Instant::now());
+            }
+        }
+    }
+
+    fn running(&self) -> bool {
+        self.game.as_ref().is_none_or(|g| g.state() == GameState::Running)
+    }
+
+    // Move cursor along given axis, wrapping on wrapping dimensions
+    fn move_cursor(&mut self, axis: usize, forward: bool) {
+        let size = self.settings.size[axis];
+        let current = self.cursor[axis];
+        self.cursor[axis] = if forward {
+            if current + 1 < size {current + 1} else if self.settings.wrap[axis] {0} else {current}
+        } else if current > 0 {
+            current - 1
+        } else if self.settings.wrap[axis] {
+            size - 1
+        } else {
+            current
+        };
+    }
+
+    fn probe(&mut self) {
+        if let Some(game) = &mut self.game {
+            if game.state() == GameState::Running {
+                game.probe_at(self.cursor, false);
+            }
+        } else {
+            self.start();
+        }
+        self.check_end();
+    }
+
+    fn mark(&mut self) {
+        if let Some(game) = &mut self.game {
+            if game.state() == GameState::Running {
+                game.mark_at(self.cursor);
+            }
+        }
+    }
+
+    fn chord(&mut self) {
+        if let Some(game) = &mut self.game {
+            if game.state() == GameState::Running {
+                game.chord_at(self.cursor);
+            }
+        }
+        self.check_end();
+    }
+
+    fn toggle_highlight(&mut self) {
+        let group = 1 << self.highlight_group;
+        if let Some(game) = &mut self.game {
+            let enabled = highlight_groups(game.cell_at(self.cursor)) & group > 0;
+            game.highlight_at(self.cursor, group, !enabled);
+        }
+    }
+
+    // Screen position (column, row) of given cell within the whole board
+    fn cell_position(&self, coords: [usize; DIMENSIONS_COUNT]) -> (usize, usize) {
+        let [ix, iy, iz, iu, iv, iw] = coords;
+        let [s_x, s_y, s_z, s_u, _, _] = self.settings.size;
+        let x_block = s_x * CELL_WIDTH;
+        let z_block = s_z * x_block + (s_z - 1) * GAP_Z;
+        let u_block = s_u * s_y + (s_u - 1) * GAP_U;
+        (iv * (z_block + GAP_V) + iz * (x_block + GAP_Z) + ix * CELL_WIDTH,
+         iw * (u_block + GAP_W) + iu * (s_y + GAP_U) + iy)
+    }
+
+    // Keep the cursor within the visible area
+    fn scroll_to_cursor(&mut self, width: usize, height: usize) {
+        let (col, row) = self.cell_position(self.cursor);
+        if col < self.scroll.0 {
+            self.scroll.0 = col;
+        } else if col + CELL_WIDTH > self.scroll.0 + width {
+            self.scroll.0 = col + CELL_WIDTH - width;
+        }
+        if row < self.scroll.1 {
+            self.scroll.1 = row;
+        } else if row + 1 > self.scroll.1 + height {
+            self.scroll.1 = row + 1 - height;
+        }
+    }
+
+    fn cell_text(&self, coords: [usize; DIMENSIONS_COUNT]) -> (String, Color, Color) {
+        let Some(game) = &self.game else {
+            return ("·".into(), Color::Grey, Color::DarkGrey);
+        };
+        let finished = game.state() != GameState::Running;
+        match game.cell_at(coords) {
+            CellState::UndiscoveredMine(_) if finished
+                => ("*".into(), if game.state() == GameState::Victory {Color::Green} else {Color::Red}, Color::DarkGrey),
+            CellState::UndiscoveredMine(_) | CellState::UndiscoveredEmpty(..)
+                => ("·".into(), Color::Grey, Color::DarkGrey),
+            CellState::MarkedMine(_)
+                => ("F".into(), if finished {Color::Green} else {Color::Red}, Color::DarkGrey),
+            CellState::MarkedEmpty(..)
+                => ("F".into(), Color::Red, if finished {Color::DarkRed} else {Color::DarkGrey}),
+            CellState::ExplodedMine(_) => ("X".into(), Color::White, Color::Red),
+            CellState::DiscoveredEmpty(mc, delta, _) => {
+                let value = if self.show_delta {delta} else {mc as i32};
+                if mc == 0 && delta == 0 {
+                    ("".into(), Color::Reset, Color::Reset)
+                } else {
+                    let color = match value {
+                        ..=-1 => Color::Magenta,
+                        0 => Color::DarkGrey,
+                        1 => Color::Blue,
+                        2 => Color::Green,
+                        3 => Color::Red,
+                        4 => Color::DarkBlue,
+                        5 => Color::DarkRed,
+                        6 => Color::Cyan,
+                        _ => Color::Yellow,
+                    };
+                    (format!("{}", value), color, Color::Reset)
+                }
+            },
+        }
+    }
+
+    fn draw(&mut self, out: &mut impl Write) -> io::Result<()> {
+        let (width, height) = terminal::size()?;
+        let (width, height) = (width as usize, (height as usize).saturating_sub(STATUS_LINES));
+        self.scroll_to_cursor(width, height);
+
+        queue!(out, ResetColor, terminal::Clear(terminal::ClearType::All))?;
+
+        let neighbors = self.game.as_ref().map(|g| g.neighbors(self.cursor)).unwrap_or_default();
+        let [s_x, s_y, s_z, s_u, s_v, s_w] = self.settings.size;
+        for iw in 0..s_w {
+        for iv in 0..s_v {
+        for iu in 0..s_u {
+        for iz in 0..s_z {
+        for iy in 0..s_y {
+        for ix in 0..s_x {
+            let coords = [ix, iy, iz, iu, iv, iw];
+            let (col, row) = self.cell_position(coords);
+            if col < self.scroll.0 || col + CELL_WIDTH > self.scroll.0 + width
+               || row < self.scroll.1 || row >= self.scroll.1 + height {
+                continue;
+            }
+
+            let (text, mut foreground, mut background) = self.cell_text(coords);
+            if neighbors.contains(&coords) {
+                background = Color::DarkCyan;
+            }
+            let groups = self.game.as_ref().map_or(0, |g| highlight_groups(g.cell_at(coords)));
+            let hidden = text.is_empty() || text == "·";
+            if groups > 0 && hidden {
+                foreground = HIGHLIGHT_COLORS[groups.trailing_zeros() as usize];
+            }
+
+            queue!(out, cursor::MoveTo((col - self.scroll.0) as u16, (row - self.scroll.1) as u16),
+                   SetForegroundColor(foreground), SetBackgroundColor(background))?;
+            if groups > 0 {
+                queue!(out, SetAttribute(Attribute::Underlined))?;
+            }
+            if coords == self.cursor {
+                queue!(out, SetAttribute(Attribute::Reverse))?;
+            }
+            let text = if groups > 0 && hidden {"#".into()} else {text};
+            queue!(out, Print(format!("{:>width$}", text, width = CELL_WIDTH)),
+                   SetAttribute(Attribute::Reset), ResetColor)?;
+        }}}}}}
+
+        // Status bar
+        let elapsed = match (self.start_time, self.end_time) {
+            (Some(start), Some(end)) => end.duration_since(start),
+            (Some(start), None) => start.elapsed(),
+            _ => Duration::ZERO,
+        };
+        let state = match self.game.as_ref().map(|g| g.state()) {
+            Some(GameState::Victory) => "You won!",
+            Some(GameState::Loss) => "You lost!",
+            Some(GameState::Running) => "",
+            None => "Probe a cell to start",
+        };
+        let status = format!("({}/{})  {}  {}  cursor [{}]  group {}  {}",
+                             self.game.as_ref().map_or(0, |g| g.marked_as_mine()),
+                             self.settings.mines,
+                             self.settings.size_description(),
+                             elapsed.hhmmss(),
+                             self.cursor.iter().map(|c| c.to_string()).collect::<Vec<_>>().join(", "),
+                             self.highlight_group + 1,
+                             state);
+        queue!(out, cursor::MoveTo(0, height as u16 + 1), Print(truncate(&status, width)),
+               cursor::MoveTo(0, height as u16 + 2), SetForegroundColor(Color::DarkGrey),
+               Print(truncate(HELP, width)), ResetColor)?;
+        out.flush()
+    }
+
+    fn run(&mut self) -> io::Result<()> {
+        let mut out = io::stdout();
+        loop {
+            self.draw(&mut out)?;
+            // Wake up regularly so the timer keeps running
+            if !event::poll(Duration::from_millis(if self.running() {500} else {5000}))? {
+                continue;
+            }
+            let Event::Key(key) = event::read()? else { continue };
+            if key.kind == KeyEventKind::Release {
+                continue;
+            }
+            let shift = key.modifiers.contains(KeyModifiers::SHIFT);
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return Ok(()),
+
+                KeyCode::Left if shift => self.move_cursor(2, false),
+                KeyCode::Right if shift => self.move_cursor(2, true),
+                KeyCode::Up if shift => self.move_cursor(3, false),
+                KeyCode::Down if shift => self.move_cursor(3, true),
+                KeyCode::Left | KeyCode::Char('a') => self.move_cursor(0, false),
+                KeyCode::Right | KeyCode::Char('d') => self.move_cursor(0, true),
+                KeyCode::Up | KeyCode::Char('w') => self.move_cursor(1, false),
+                KeyCode::Down | KeyCode::Char('s') => self.move_cursor(1, true),
+                KeyCode::Char('j') => self.move_cursor(2, false),
+                KeyCode::Char('l') => self.move_cursor(2, true),
+                KeyCode::Char('i') => self.move_cursor(3, false),
+                KeyCode::Char('k') => self.move_cursor(3, true),
+                KeyCode::Char('J') => self.move_cursor(4, false),
+                KeyCode::Char('L') => self.move_cursor(4, true),
+                KeyCode::Char('I') => self.move_cursor(5, false),
+                KeyCode::Char('K') => self.move_cursor(5, true),
+
+                KeyCode::Char(' ') | KeyCode::Enter => self.probe(),
+                KeyCode::Char('f') => self.mark(),
+                KeyCode::Char('c') => self.chord(),
+                KeyCode::Char('h') => self.toggle_highlight(),
+                KeyCode::Char(d @ '1'..='8') => self.highlight_group = d as usize - '1' as usize,
+                KeyCode::Char('t') => self.show_delta = !self.show_delta,
+                KeyCode::Char('n') => self.reset(),
+                _ => {}
+            }
+        }
+    }
+}
+
+fn highlight_groups(cell: CellState) -> u8 {
+    match cell {
+        CellState::UndiscoveredMine(g) | CellState::MarkedMine(g) | CellState::ExplodedMine(g)
+        | CellState::UndiscoveredEmpty(.., g) | CellState::MarkedEmpty(.., g)
+        | CellState::DiscoveredEmpty(.., g) => g,
+    }
+}
+
+fn truncate(text: &str, width: usize) -> String {
+    text.chars().take(width).collect()
+}
+
+fn parse_args() -> Result<InitialGameSettings, String> {
+    let mut settings = InitialGameSettings { name: "Terminal".into(), ..Default::default() };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--help" || arg == "-h" {
+            return Err(USAGE.into());
+        }
+        let value = args.next().ok_or_else(|| format!("Missing value for `{}`\n\n{}", arg, USAGE))?;
+        match arg.as_str() {
+            "--size" => {
+                settings.size = config::parse_size(&value)
+                    .ok_or_else(|| format!("Invalid value of `--size`: {}", value))?;
+            },
+            "--wrap" => {
+                settings.wrap = config::parse_wrap(&value)
+                    .ok_or_else(|| format!("Invalid value of `--wrap`: {}", value))?;
+            },
+            "--mines" => {
+                settings.mines = value.parse().map_err(|_| format!("Invalid value of `--mines`: {}", value))?;
+            },
+            "--seed" => {
+                u64::from_str_radix(&value, 16).map_err(|_| format!("Invalid value of `--seed`: {}", value))?;
+                settings.seed = Some(value);
+            },
+            _ => return Err(format!("Unknown argument `{}`\n\n{}", arg, USAGE)),
+        }
+    }
+    let total: usize = settings.size.iter().product();
+    if settings.mines == 0 || settings.mines as usize >= total {
+        return Err(format!("Number of mines has to be between 1 and {}", total - 1));
+    }
+    Ok(settings)
+}
+
+fn main() {
+    let settings = match parse_args() {
+        Ok(settings) => settings,
+        Err(message) => {
+            eprintln!("{}", message);
+            std::process::exit(2);
+        }
+    };
+
+    let result = TerminalGuard::new().and_then(|_guard| MinesweeperTerminal::new(settings).run());
+    if let Err(error) = result {
+        eprintln!("Terminal error: {}", error);
+        std::process::exit(1);
+    }
+}
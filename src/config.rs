@@ -0,0 +1,807 @@
+use crate::layout::BoardLayout;
+use crate::minesweeper_model::{DIMENSIONS_COUNT, InitialGameSettings};
+use std::fmt;
+use std::ops::Range;
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::PathBuf;
+use toml_edit::{Array, ArrayOfTables, DocumentMut, ImDocument, Item, Table, TableLike, Value};
+
+// Preferences the player can change from the UI, these are written back to config.toml
+#[derive(Clone, Debug, PartialEq)]
+pub struct Preferences {
+    pub show_timer_miliseconds: bool,
+    pub show_delta: bool,
+    pub show_neighbors: bool,
+    pub unlimited_zoom: bool,
+    pub probe_marked: bool,
+    pub highlight_colors: [String; 8], // "#RRGGBB"
+    pub layout: BoardLayout,
+    pub auto_layout: bool,
+    pub show_minimap: bool,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Severity {
+    Warning, // value was adjusted or ignored
+    Error,   // value could not be used at all, default is used instead
+}
+
+// Single mistake found in the configuration file
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConfigProblem {
+    pub severity: Severity,
+    pub line: Option<usize>, // from 1
+    pub key: String,         // e.g. `preset[2].size`, empty for the file as a whole
+    pub message: String,
+}
+
+impl fmt::Display for ConfigProblem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", if self.severity == Severity::Error {"Error"} else {"Warning"})?;
+        match (self.line, self.key.is_empty()) {
+            (Some(line), false) => write!(f, " (line {}, `{}`)", line, self.key)?,
+            (Some(line), true) => write!(f, " (line {})", line)?,
+            (None, false) => write!(f, " (`{}`)", self.key)?,
+            (None, true) => {},
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+// Contents of config.toml, every value is validated (missing or invalid values are left at their defaults)
+#[derive(Clone, Debug, PartialEq)]
+pub struct Config {
+    pub show_timer_miliseconds: bool,
+    pub show_delta: bool,
+    pub show_neighbors: bool,
+    pub unlimited_zoom: bool,
+    pub probe_marked: bool,
+    pub tile_spacings: [f32; DIMENSIONS_COUNT],
+    pub layout: BoardLayout,
+    pub auto_layout: bool, // layout is picked to fit the window, `layout` is only used when this is off
+    pub show_minimap: bool,
+    pub highlight_colors: [[u8; 4]; HIGHLIGHT_GROUPS], // unmultiplied RGBA
+    pub default_preset: Option<usize>,
+    pub presets: Vec<InitialGameSettings>,
+    pub shortcuts: [Shortcut; SHORTCUT_COUNT], // in the order of `SHORTCUT_ACTIONS`
+}
+
+pub const HIGHLIGHT_GROUPS: usize = 8;
+
+pub const SHORTCUT_COUNT: usize = 36;
+
+// Keys of the `[shortcuts]` table and what they do
+pub const SHORTCUT_ACTIONS: [(&str, &str); SHORTCUT_COUNT] = [
+    ("probe_mark", "Probe/Mark tool"),
+    ("highlighter", "Highlighter tool"),
+    ("highlight_group_1", "Toggle highlight group 1"),
+    ("highlight_group_2", "Toggle highlight group 2"),
+    ("highlight_group_3", "Toggle highlight group 3"),
+    ("highlight_group_4", "Toggle highlight group 4"),
+    ("highlight_group_5", "Toggle highlight group 5"),
+    ("highlight_group_6", "Toggle highlight group 6"),
+    ("highlight_group_7", "Toggle highlight group 7"),
+    ("highlight_group_8", "Toggle highlight group 8"),
+    ("reset_view", "Reset view"),
+    ("zoom_to_fit", "Zoom to fit"),
+    ("transpose_layout", "Swap across and down axes"),
+    ("auto_layout", "Auto layout"),
+    ("slice_view", "Slice explorer"),
+    ("minimap", "Minimap"),
+    ("go_to", "Go to coordinates"),
+    ("find_open_number", "Find next number with mines left"),
+    ("find_safe_cell", "Find next cell next to a solved number"),
+    ("highlight_neighbors", "Highlight undiscovered neighbors"),
+    // Keyboard cursor, a pair of keys per axis
+    ("cursor_x_minus", "Move cursor along x −1"),
+    ("cursor_x_plus", "Move cursor along x +1"),
+    ("cursor_y_minus", "Move cursor along y −1"),
+    ("cursor_y_plus", "Move cursor along y +1"),
+    ("cursor_z_minus", "Move cursor along z −1"),
+    ("cursor_z_plus", "Move cursor along z +1"),
+    ("cursor_u_minus", "Move cursor along u −1"),
+    ("cursor_u_plus", "Move cursor along u +1"),
+    ("cursor_v_minus", "Move cursor along v −1"),
+    ("cursor_v_plus", "Move cursor along v +1"),
+    ("cursor_w_minus", "Move cursor along w −1"),
+    ("cursor_w_plus", "Move cursor along w +1"),
+    ("cursor_probe", "Probe at cursor"),
+    ("cursor_mark", "Mark at cursor"),
+    ("cursor_chord", "Chord at cursor"),
+    ("cursor_highlight", "Toggle highlight at cursor"),
+];
+
+// Key with modifiers, written as e.g. "Ctrl+Shift+Q" in config.toml.
+// Key names are the ones egui uses, `Cmd` is Ctrl on Windows and Linux and ⌘ on Mac.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Shortcut {
+    pub ctrl: bool,
+    pub command: bool,
+    pub alt: bool,
+    pub shift: bool,
+    pub key: String,
+}
+
+impl Shortcut {
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        // `+` itself may be the key, e.g. "Ctrl++"
+        let (modifiers, key) = match text.strip_suffix('+') {
+            Some(rest) if rest.is_empty() || rest.ends_with('+') => (rest.strip_suffix('+').unwrap_or(""), "+"),
+            _ => text.rsplit_once('+').unwrap_or(("", text)),
+        };
+        let mut shortcut = Shortcut { key: key.trim().into(), ..Default::default() };
+        if shortcut.key.is_empty() {
+            return None;
+        }
+        for modifier in modifiers.split('+').filter(|_| !modifiers.is_empty()) {
+            let flag = match modifier.trim().to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => &mut shortcut.ctrl,
+                "cmd" | "command" => &mut shortcut.command,
+                "alt" | "option" => &mut shortcut.alt,
+                "shift" => &mut shortcut.shift,
+                _ => return None,
+            };
+            *flag = true;
+        }
+        Some(shortcut)
+    }
+}
+
+impl fmt::Display for Shortcut {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (on, name) in [(self.ctrl, "Ctrl+"), (self.command, "Cmd+"), (self.alt, "Alt+"), (self.shift, "Shift+")] {
+            if on {
+                f.write_str(name)?;
+            }
+        }
+        f.write_str(&self.key)
+    }
+}
+
+// Built-in configuration, the user's file only needs to contain what it changes
+pub const DEFAULT_CONFIG: &str = include_str!("../config.toml");
+
+// Configuration file to use, unless one was `given` on the command line:
+// config.toml in the working directory if present, otherwise the one in the user's config directory.
+// The returned file may not exist yet.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn config_file_path(given: Option<PathBuf>) -> Option<PathBuf> {
+    given.or_else(|| Some(PathBuf::from("config.toml")).filter(|p| p.is_file()))
+         .or_else(|| dirs::config_dir().map(|d| d.join("minesweeper6d").join("config.toml")))
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            show_timer_miliseconds: false,
+            show_delta: true,
+            show_neighbors: true,
+            unlimited_zoom: false,
+            probe_marked: false,
+            tile_spacings: [0.0, 0.0, 10.0, 10.0, 20.0, 20.0],
+            layout: BoardLayout::default(),
+            auto_layout: false,
+            show_minimap: false,
+            // YELLOW, BROWN, LIGHT_GREEN, WHITE, KHAKI, DARK_BLUE, DARK_GREEN, GOLD
+            highlight_colors: [[255, 255, 0, 255], [165, 42, 42, 255], [144, 238, 144, 255], [255, 255, 255, 255],
+                               [240, 230, 140, 255], [0, 0, 139, 255], [0, 100, 0, 255], [255, 215, 0, 255]],
+            default_preset: None,
+            presets: vec![],
+            shortcuts: ["Q", "W", "1", "2", "3", "4", "5", "6", "7", "8", "D", "F", "T", "L", "S", "N", "G", "O", "P", "U",
+                        "Left", "Right", "Up", "Down", "Shift+Left", "Shift+Right", "Shift+Up", "Shift+Down",
+                        "Cmd+Left", "Cmd+Right", "Cmd+Up", "Cmd+Down", "Space", "M", "C", "H"]
+                           .map(|s| Shortcut::parse(s).expect("built-in shortcuts are valid")),
+        }
+    }
+}
+
+impl Config {
+    // Never fails, problems are collected and returned alongside whatever could be loaded
+    pub fn parse(config_text: &str) -> (Self, Vec<ConfigProblem>) {
+        let mut config = Config::default();
+        let problems = config.apply(config_text);
+        (config, problems)
+    }
+
+    // Built-in configuration with the user's file layered over it
+    pub fn layered(user_text: &str) -> (Self, Vec<ConfigProblem>) {
+        let (mut config, _) = Config::parse(DEFAULT_CONFIG);
+        let problems = config.apply(user_text);
+        (config, problems)
+    }
+
+    // Override values present in given text, everything else is kept.
+    // Presets replace the ones with the same name, new ones are added at the end,
+    // unless `builtin_presets = false` says the text has the whole list.
+    pub fn apply(&mut self, config_text: &str) -> Vec<ConfigProblem> {
+        let mut parser = Parser { text: config_text, problems: vec![] };
+
+        let document = match ImDocument::parse(config_text) {
+            Ok(document) => document,
+            Err(e) => {
+                parser.report(Severity::Error, e.span(), "", e.message().trim_end().into());
+                return parser.problems;
+            }
+        };
+
+        let mut default_preset = None;
+        let mut builtin_presets = true;
+        let mut presets = vec![];
+        for (key, item) in document.iter() {
+            let span = item.span();
+            match key {
+                "show_timer_miliseconds" => parser.bool(item, key, &mut self.show_timer_miliseconds),
+                "show_delta" => parser.bool(item, key, &mut self.show_delta),
+                "show_neighbors" => parser.bool(item, key, &mut self.show_neighbors),
+                "unlimited_zoom" => parser.bool(item, key, &mut self.unlimited_zoom),
+                "probe_marked" => parser.bool(item, key, &mut self.probe_marked),
+                "tile_spacings" => parser.tile_spacings(item, &mut self.tile_spacings),
+                "layout" => match item.as_str().map(BoardLayout::parse) {
+                    Some(Some(layout)) => self.layout = layout,
+                    _ => parser.report(Severity::Error, span, key,
+                                       "expected axes going across and down, innermost first, e.g. \"xzv/yuw\"".into()),
+                },
+                "auto_layout" => parser.bool(item, key, &mut self.auto_layout),
+                "show_minimap" => parser.bool(item, key, &mut self.show_minimap),
+                "highlight_colors" => parser.highlight_colors(item, &mut self.highlight_colors),
+                "default_preset" => match item.as_integer() {
+                    Some(i) => default_preset = Some((i, span)),
+                    None => parser.report(Severity::Error, span, key, "expected a whole number".into()),
+                },
+                "shortcuts" => parser.shortcuts(item, &mut self.shortcuts),
+                "builtin_presets" => parser.bool(item, key, &mut builtin_presets),
+                "preset" => presets = parser.presets(item),
+                _ => parser.report(Severity::Warning, span, key, "unknown key, ignored".into()),
+            }
+        }
+
+        if !builtin_presets {
+            // The whole list as it is, presets with the same name included
+            self.presets = presets;
+            self.default_preset = None;
+        } else {
+            for preset in presets {
+                match self.presets.iter_mut().find(|p| p.name == preset.name) {
+                    Some(existing) => *existing = preset,
+                    None => self.presets.push(preset),
+                }
+            }
+        }
+
+        // Checked at the end, presets may come after it
+        if let Some((i, span)) = default_preset {
+            if i >= 0 && (i as usize) < self.presets.len() {
+                self.default_preset = Some(i as usize);
+            } else {
+                parser.report(Severity::Warning, span, "default_preset",
+                              format!("there are only {} presets (numbered from 0)", self.presets.len()));
+            }
+        }
+
+        parser.problems
+    }
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    problems: Vec<ConfigProblem>,
+}
+
+impl Parser<'_> {
+    fn report(&mut self, severity: Severity, span: Option<Range<usize>>, key: &str, message: String) {
+        let line = span.map(|s| self.text[..s.start.min(self.text.len())].matches('\n').count() + 1);
+        self.problems.push(ConfigProblem { severity, line, key: key.into(), message });
+    }
+
+    fn bool(&mut self, item: &Item, key: &str, target: &mut bool) {
+        match item.as_bool() {
+            Some(b) => *target = b,
+            None => self.report(Severity::Error, item.span(), key, "expected `true` or `false`".into()),
+        }
+    }
+
+    fn tile_spacings(&mut self, item: &Item, target: &mut [f32; DIMENSIONS_COUNT]) {
+        let key = "tile_spacings";
+        let Some(array) = item.as_array() else {
+            self.report(Severity::Error, item.span(), key, "expected an array of numbers".into());
+            return;
+        };
+        if array.len() > DIMENSIONS_COUNT {
+            self.report(Severity::Warning, item.span(), key,
+                        format!("only {} values are used, {} found", DIMENSIONS_COUNT, array.len()));
+        }
+        for (ii, value) in array.iter().take(DIMENSIONS_COUNT).enumerate() {
+            let number = value.as_float().or_else(|| value.as_integer().map(|i| i as f64));
+            match number {
+                Some(f) if f >= 0.0 => target[ii] = f as f32,
+                Some(_) => self.report(Severity::Warning, value.span(), key,
+                                       format!("value at index {} can't be negative, ignored", ii)),
+                None => self.report(Severity::Error, value.span(), key,
+                                    format!("value at index {} is not a number", ii)),
+            }
+        }
+    }
+
+    fn highlight_colors(&mut self, item: &Item, target: &mut [[u8; 4]; HIGHLIGHT_GROUPS]) {
+        let key = "highlight_colors";
+        let Some(array) = item.as_array() else {
+            self.report(Severity::Error, item.span(), key, "expected an array of colours like \"#FFFF00\"".into());
+            return;
+        };
+        if array.len() != HIGHLIGHT_GROUPS {
+            self.report(Severity::Warning, item.span(), key,
+                        format!("should have {} colours, {} found", HIGHLIGHT_GROUPS, array.len()));
+        }
+        for (ii, value) in array.iter().take(HIGHLIGHT_GROUPS).enumerate() {
+            match value.as_str().and_then(parse_color) {
+                Some(color) => target[ii] = color,
+                None => self.report(Severity::Error, value.span(), key,
+                                    format!("value at index {} is not a colour like \"#FFFF00\"", ii)),
+            }
+        }
+    }
+
+    fn shortcuts(&mut self, item: &Item, target: &mut [Shortcut; SHORTCUT_COUNT]) {
+        let Some(table) = item.as_table_like() else {
+            self.report(Severity::Error, item.span(), "shortcuts", "expected a `[shortcuts]` table".into());
+            return;
+        };
+        for (name, item) in table.iter() {
+            let key = format!("shortcuts.{}", name);
+            let Some(index) = SHORTCUT_ACTIONS.iter().position(|(action, _)| *action == name) else {
+                self.report(Severity::Warning, item.span(), &key, "unknown action, ignored".into());
+                continue;
+            };
+            match item.as_str().map(Shortcut::parse) {
+                Some(Some(shortcut)) => target[index] = shortcut,
+                Some(None) => self.report(Severity::Error, item.span(), &key,
+                                          "expected a key with optional modifiers, e.g. \"Ctrl+Q\"".into()),
+                None => self.report(Severity::Error, item.span(), &key, "expected a string".into()),
+            }
+        }
+    }
+
+    fn presets(&mut self, item: &Item) -> Vec<InitialGameSettings> {
+        // Either `[[preset]]` tables or an inline array of tables
+        let tables: Vec<(&dyn TableLike, Option<Range<usize>>)> = match item {
+            Item::ArrayOfTables(array) => array.iter().map(|t| (t as &dyn TableLike, t.span())).collect(),
+            Item::Value(Value::Array(array)) => {
+                let mut tables = Vec::new();
+                for value in array.iter() {
+                    match value.as_inline_table() {
+                        Some(t) => tables.push((t as &dyn TableLike, t.span())),
+                        None => self.report(Severity::Error, value.span(), "preset", "expected a table".into()),
+                    }
+                }
+                tables
+            },
+            _ => {
+                self.report(Severity::Error, item.span(), "preset", "expected `[[preset]]` tables".into());
+                return vec![];
+            }
+        };
+        tables.into_iter().enumerate().map(|(ii, (table, span))| self.preset(ii, table, span)).collect()
+    }
+
+    fn preset(&mut self, index: usize, table: &dyn TableLike, span: Option<Range<usize>>) -> InitialGameSettings {
+        let mut igs = InitialGameSettings { name: format!("Preset {}", index), ..Default::default() };
+        let mut mines = None;
+        for (name, item) in table.iter() {
+            let key = format!("preset[{}].{}", index, name);
+            let span = item.span();
+            match name {
+                "name" => match item.as_str() {
+                    Some(s) => igs.name = s.into(),
+                    None => self.report(Severity::Error, span, &key, "expected a string".into()),
+                },
+                "size" => {
+                    let Some(a) = item.as_array() else {
+                        self.report(Severity::Error, span, &key, "expected an array of whole numbers".into());
+                        continue;
+                    };
+                    if a.len() != DIMENSIONS_COUNT {
+                        self.report(Severity::Warning, span, &key,
+                                    format!("should be array of {} elements, {} found", DIMENSIONS_COUNT, a.len()));
+                    }
+                    for (ii, value) in a.iter().take(DIMENSIONS_COUNT).enumerate() {
+                        match value.as_integer() {
+                            Some(i) if (1..=100).contains(&i) => igs.size[ii] = i as usize,
+                            Some(i) => {
+                                igs.size[ii] = i.clamp(1, 100) as usize;
+                                self.report(Severity::Warning, value.span(), &key,
+                                            format!("value at index {} has to be between 1 and 100", ii));
+                            },
+                            None => self.report(Severity::Error, value.span(), &key,
+                                                format!("value at index {} is invalid", ii)),
+                        }
+                    }
+                },
+                "wrap" => {
+                    let Some(a) = item.as_array() else {
+                        self.report(Severity::Error, span, &key, "expected an array of `true`/`false`".into());
+                        continue;
+                    };
+                    if a.len() != DIMENSIONS_COUNT {
+                        self.report(Severity::Warning, span, &key,
+                                    format!("should be array of {} elements, {} found", DIMENSIONS_COUNT, a.len()));
+                    }
+                    for (ii, value) in a.iter().take(DIMENSIONS_COUNT).enumerate() {
+                        match value.as_bool() {
+                            Some(b) => igs.wrap[ii] = b,
+                            None => self.report(Severity::Error, value.span(), &key,
+                                                format!("value at index {} is invalid", ii)),
+                        }
+                    }
+                },
+                // Checked once the size is known
+                "mines" => match item.as_integer() {
+                    Some(i) => mines = Some((i, span, key)),
+                    None => self.report(Severity::Error, span, &key, "expected a whole number".into()),
+                },
+                "seed" => match item.as_str() {
+                    Some(s) if u64::from_str_radix(s, 16).is_ok() => igs.seed = Some(s.into()),
+                    _ => self.report(Severity::Error, span, &key, "expected a hexadecimal number in quotes".into()),
+                },
+                _ => self.report(Severity::Warning, span, &key, "unknown key, ignored".into()),
+            }
+        }
+
+        let max_mines = (igs.size.iter().product::<usize>() - 1) as i64;
+        match mines {
+            Some((i, span, key)) => {
+                igs.mines = i.clamp(1, max_mines.max(1)) as u32;
+                if !(1..=max_mines).contains(&i) {
+                    self.report(Severity::Warning, span, &key,
+                                format!("has to be between 1 and {}, {} is used", max_mines, igs.mines));
+                }
+            },
+            None => {
+                igs.mines = igs.mines.min(max_mines.max(1) as u32);
+                self.report(Severity::Warning, span, &format!("preset[{}]", index),
+                            format!("`mines` is missing, {} is used", igs.mines));
+            }
+        }
+        igs
+    }
+}
+
+// "#RRGGBB" or "#RRGGBBAA"
+fn parse_color(hex: &str) -> Option<[u8; 4]> {
+    let digits = hex.strip_prefix('#')?;
+    if !(digits.len() == 6 || digits.len() == 8) || !digits.is_ascii() {
+        return None;
+    }
+    let mut color = [255; 4];
+    for (ii, c) in color.iter_mut().enumerate().take(digits.len() / 2) {
+        *c = u8::from_str_radix(&digits[2*ii..2*ii + 2], 16).ok()?;
+    }
+    Some(color)
+}
+
+// Update preferences in the text of a user's configuration file, which is layered over the built-in one.
+// Only values that differ from the built-in configuration are written, keys changed back to it are removed.
+// Everything else (comments, formatting, presets, unknown keys) is kept as it was.
+pub fn write_preferences(config_text: &str, preferences: &Preferences) -> Result<String, toml_edit::TomlError> {
+    let mut document: DocumentMut = config_text.parse()?;
+    let (builtin, _) = Config::parse(DEFAULT_CONFIG);
+    let mut current = builtin.clone();
+    current.apply(config_text);
+
+    update_value(&mut document, "show_timer_miliseconds", preferences.show_timer_miliseconds,
+                 builtin.show_timer_miliseconds, current.show_timer_miliseconds);
+    update_value(&mut document, "show_delta", preferences.show_delta, builtin.show_delta, current.show_delta);
+    update_value(&mut document, "show_neighbors", preferences.show_neighbors, builtin.show_neighbors, current.show_neighbors);
+    update_value(&mut document, "unlimited_zoom", preferences.unlimited_zoom, builtin.unlimited_zoom, current.unlimited_zoom);
+    update_value(&mut document, "probe_marked", preferences.probe_marked, builtin.probe_marked, current.probe_marked);
+    update_value(&mut document, "layout", preferences.layout.to_string(),
+                 builtin.layout.to_string(), current.layout.to_string());
+    update_value(&mut document, "auto_layout", preferences.auto_layout, builtin.auto_layout, current.auto_layout);
+    update_value(&mut document, "show_minimap", preferences.show_minimap, builtin.show_minimap, current.show_minimap);
+
+    // Colors are compared by value, not by how they are written
+    let colors = preferences.highlight_colors.each_ref().map(|c| parse_color(c));
+    if colors != builtin.highlight_colors.map(Some) {
+        set_value(&mut document, "highlight_colors",
+                  Array::from_iter(preferences.highlight_colors.iter().map(|c| c.as_str())).into());
+    } else if current.highlight_colors != builtin.highlight_colors {
+        document.remove("highlight_colors");
+    }
+    Ok(to_string_like(&document, config_text))
+}
+
+// Write `value` unless it is the built-in one, then remove the key if the file still has a different value
+fn update_value<T: PartialEq + Into<Value>>(table: &mut Table, key: &str, value: T, builtin: T, current: T) {
+    if value != builtin {
+        set_value(table, key, value.into());
+    } else if current != builtin {
+        table.remove(key);
+    }
+}
+
+// Replace presets in the text of a configuration file by the given list.
+// The built-in presets are switched off, so the list is used exactly as it is (order and deletions included).
+pub fn write_presets(config_text: &str, presets: &[InitialGameSettings], default_preset: Option<usize>)
+        -> Result<String, toml_edit::TomlError> {
+    let mut document: DocumentMut = config_text.parse()?;
+    set_value(&mut document, "builtin_presets", false.into());
+    match default_preset {
+        Some(i) => set_value(&mut document, "default_preset", (i as i64).into()),
+        None => {document.remove("default_preset");},
+    }
+
+    // Comments above a preset stay with it, even when it was moved
+    let old_tables: Vec<Table> = document.get("preset").and_then(Item::as_array_of_tables)
+                                         .map_or(vec![], |a| a.iter().cloned().collect());
+    let mut tables = ArrayOfTables::new();
+    for preset in presets {
+        let mut table = Table::new();
+        table.insert("name", preset.name.as_str().into());
+        table.insert("size", Item::Value(Array::from_iter(preset.size.iter().map(|&s| s as i64)).into()));
+        if preset.wrap.iter().any(|&w| w) {
+            table.insert("wrap", Item::Value(Array::from_iter(preset.wrap).into()));
+        }
+        table.insert("mines", (preset.mines as i64).into());
+        if let Some(seed) = &preset.seed {
+            table.insert("seed", seed.as_str().into());
+        }
+        if let Some(old) = old_tables.iter().find(|t| t.get("name").and_then(Item::as_str) == Some(&preset.name)) {
+            *table.decor_mut() = old.decor().clone();
+        }
+        tables.push(table);
+    }
+    document.insert("preset", Item::ArrayOfTables(tables));
+    Ok(to_string_like(&document, config_text))
+}
+
+// Write shortcuts into the text of a configuration file.
+// Only the ones that differ from the built-in configuration or are already in the file are written.
+pub fn write_shortcuts(config_text: &str, shortcuts: &[Shortcut; SHORTCUT_COUNT])
+        -> Result<String, toml_edit::TomlError> {
+    let mut document: DocumentMut = config_text.parse()?;
+    let defaults = Config::layered("").0.shortcuts;
+    let mut table = match document.remove("shortcuts") {
+        Some(Item::Table(table)) => table,
+        _ => Table::new(),
+    };
+    for (ii, (action, _)) in SHORTCUT_ACTIONS.iter().enumerate() {
+        if table.contains_key(action) || shortcuts[ii] != defaults[ii] {
+            set_value(&mut table, action, shortcuts[ii].to_string().into());
+        }
+    }
+    if !table.is_empty() {
+        document.insert("shortcuts", Item::Table(table));
+    }
+    Ok(to_string_like(&document, config_text))
+}
+
+// Keep Windows line endings of the original text, toml_edit writes plain '\n'
+fn to_string_like(document: &DocumentMut, config_text: &str) -> String {
+    let written = document.to_string();
+    if config_text.contains("\r\n") {
+        written.replace("\r\n", "\n").replace('\n', "\r\n")
+    } else {
+        written
+    }
+}
+
+// Replace value of a key in the table, keeping the comments around it
+// (and around array elements, if the array keeps its length)
+fn set_value(table: &mut Table, key: &str, mut value: Value) {
+    match table.get_mut(key).and_then(Item::as_value_mut) {
+        Some(old) => {
+            if let (Value::Array(old_array), Value::Array(new_array)) = (&*old, &mut value) {
+                if old_array.len() == new_array.len() {
+                    for (old_element, new_element) in old_array.iter().zip(new_array.iter_mut()) {
+                        *new_element.decor_mut() = old_element.decor().clone();
+                    }
+                    new_array.set_trailing(old_array.trailing().clone());
+                    new_array.set_trailing_comma(old_array.trailing_comma());
+                }
+            }
+            *value.decor_mut() = old.decor().clone();
+            *old = value;
+        },
+        None => {
+            table.insert(key, Item::Value(value));
+        }
+    }
+}
+
+// Parse comma separated dimensions given on the command line, e.g. "4,4,3,4,1,1"
+pub fn parse_size(value: &str) -> Option<[usize; DIMENSIONS_COUNT]> {
+    parse_list(value, |v| v.parse::<usize>().ok().filter(|s| (1..=100).contains(s)))
+}
+
+// Parse comma separated wrapping flags given on the command line, e.g. "0,0,0,1,0,0"
+pub fn parse_wrap(value: &str) -> Option<[bool; DIMENSIONS_COUNT]> {
+    parse_list(value, |v| match v {
+        "1" | "true" | "w" => Some(true),
+        "0" | "false" | "" => Some(false),
+        _ => None,
+    })
+}
+
+fn parse_list<T>(value: &str, parse: impl Fn(&str) -> Option<T>) -> Option<[T; DIMENSIONS_COUNT]> {
+    let items: Vec<T> = value.split(',').map(|v| parse(v.trim())).collect::<Option<_>>()?;
+    items.try_into().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_default_config() {
+        let (config, problems) = Config::parse(DEFAULT_CONFIG);
+        assert_eq!(problems, vec![]);
+        let elevator = config.presets.iter().find(|p| p.name == "4D Minesweeper - Elevator").unwrap();
+        assert_eq!(elevator.size, [4, 4, 3, 4, 1, 1]);
+        assert_eq!(elevator.wrap, [false, false, false, true, false, false]);
+        assert_eq!(elevator.mines, 15);
+        assert_eq!(config.default_preset, Some(6));
+        assert_eq!(config.highlight_colors[5], [0x00, 0x00, 0x88, 0xFF]);
+
+        assert_eq!(parse_size("4, 4,3,4,1,1"), Some([4, 4, 3, 4, 1, 1]));
+        assert_eq!(parse_size("4,4,3"), None);
+        assert_eq!(parse_wrap("0,0,0,1,0,0"), Some([false, false, false, true, false, false]));
+    }
+
+    #[test]
+    fn test_config_problems() {
+        let text = "show_delta = 1\n\
+                    highlight_colors = [\"#FF0000\", \"red\"]\n\
+                    tile_spacings = [0, 0, -5]\n\
+                    default_preset = 3\n\
+                    colour = 5\n\
+                    [[preset]]\n\
+                    size = [9, 9, 1, 1, 1, 1]\n\
+                    mines = 500\n";
+        let (config, problems) = Config::parse(text);
+        let found: Vec<_> = problems.iter().map(|p| (p.severity, p.line, p.key.as_str())).collect();
+        assert_eq!(found, vec![
+            (Severity::Error, Some(1), "show_delta"),
+            (Severity::Warning, Some(2), "highlight_colors"),
+            (Severity::Error, Some(2), "highlight_colors"),
+            (Severity::Warning, Some(3), "tile_spacings"),
+            (Severity::Warning, Some(5), "colour"),
+            (Severity::Warning, Some(8), "preset[0].mines"),
+            (Severity::Warning, Some(4), "default_preset"),
+        ]);
+        // Everything else is loaded or left at defaults
+        assert!(config.show_delta);
+        assert_eq!(config.highlight_colors[0], [255, 0, 0, 255]);
+        assert_eq!(config.highlight_colors[1], Config::default().highlight_colors[1]);
+        assert_eq!(config.tile_spacings, [0.0, 0.0, 10.0, 10.0, 20.0, 20.0]);
+        assert_eq!(config.presets[0].mines, 80);
+
+        let (_, problems) = Config::parse("show_delta = \n");
+        assert_eq!(problems.len(), 1);
+        assert_eq!((problems[0].severity, problems[0].line), (Severity::Error, Some(1)));
+    }
+
+    #[test]
+    fn test_layered_config() {
+        let (config, problems) = Config::layered("show_delta = false\n\
+                                                  [[preset]]\n\
+                                                  name = \"2D Minesweeper - Beginner\"\n\
+                                                  size = [8, 8, 1, 1, 1, 1]\n\
+                                                  mines = 10\n\
+                                                  [[preset]]\n\
+                                                  name = \"Mine\"\n\
+                                                  size = [5, 5, 5, 1, 1, 1]\n\
+                                                  mines = 12\n");
+        assert_eq!(problems, vec![]);
+        let (defaults, _) = Config::parse(DEFAULT_CONFIG);
+        assert!(!config.show_delta);
+        assert_eq!(config.show_neighbors, defaults.show_neighbors);
+        assert_eq!(config.default_preset, defaults.default_preset);
+        assert_eq!(config.presets.len(), defaults.presets.len() + 1);
+        assert_eq!(config.presets[0].size, [8, 8, 1, 1, 1, 1]);
+        assert_eq!(config.presets.last().unwrap().name, "Mine");
+    }
+
+    #[test]
+    fn test_write_preferences() {
+        let original = DEFAULT_CONFIG;
+        let mut preferences = Preferences {
+            show_timer_miliseconds: false,
+            show_delta: true,
+            show_neighbors: true,
+            unlimited_zoom: false,
+            probe_marked: false,
+            highlight_colors: ["#FFFF00", "#A52A2A", "#90EE90", "#FFFFFF", "#F0E68C", "#000088", "#006400", "#FFD700"]
+                                  .map(String::from),
+            layout: BoardLayout::default(),
+            auto_layout: false,
+            show_minimap: false,
+        };
+        // Writing the values that are already there changes nothing
+        assert_eq!(write_preferences(original, &preferences).unwrap(), original);
+
+        preferences.unlimited_zoom = true;
+        preferences.highlight_colors[1] = "#123456".into();
+        preferences.layout = BoardLayout::parse("yzv/xuw").unwrap();
+        preferences.show_minimap = true;
+        let written = write_preferences(original, &preferences).unwrap();
+        assert!(written.contains("unlimited_zoom = true # enabling may lead"));
+        assert_eq!(Config::parse(&written).0.layout.across, vec![1, 2, 4]);
+        assert!(Config::parse(&written).0.show_minimap);
+        assert!(written.contains(r##"[ "#FFFF00", "#123456", "#90EE90","##));
+        assert_eq!(Config::parse(&written).0.presets, Config::parse(original).0.presets);
+
+        // Missing keys are added, but only the ones that differ from the built-in configuration
+        let written = write_preferences("# nothing here\n", &preferences).unwrap();
+        assert!(written.contains("# nothing here"));
+        assert!(Config::layered(&written).0.unlimited_zoom);
+        assert!(written.contains("highlight_colors"));
+        assert!(!written.contains("show_delta"));
+
+        // Keys changed back to the built-in values are removed
+        preferences.unlimited_zoom = false;
+        preferences.highlight_colors[1] = "#a52a2a".into();
+        let written = write_preferences(&written, &preferences).unwrap();
+        assert!(!written.contains("unlimited_zoom"));
+        assert!(!written.contains("highlight_colors"));
+        assert!(written.contains("show_minimap = true"));
+    }
+
+    #[test]
+    fn test_shortcuts() {
+        let shortcut = Shortcut::parse("ctrl + Shift+Q").unwrap();
+        assert_eq!(shortcut, Shortcut { ctrl: true, shift: true, key: "Q".into(), ..Default::default() });
+        assert_eq!(shortcut.to_string(), "Ctrl+Shift+Q");
+        assert_eq!(Shortcut::parse("Cmd++").unwrap().to_string(), "Cmd++");
+        assert_eq!(Shortcut::parse("+").unwrap().key, "+");
+        assert_eq!(Shortcut::parse("Ctrl+"), None);
+        assert_eq!(Shortcut::parse("Hyper+Q"), None);
+
+        let (config, problems) = Config::layered("[shortcuts]\nreset_view = \"Alt+R\"\nfly = \"X\"\nhighlighter = 5\n");
+        let found: Vec<_> = problems.iter().map(|p| (p.severity, p.line, p.key.as_str())).collect();
+        assert_eq!(found, vec![(Severity::Warning, Some(3), "shortcuts.fly"),
+                               (Severity::Error, Some(4), "shortcuts.highlighter")]);
+        assert_eq!(config.shortcuts[10].to_string(), "Alt+R");
+        assert_eq!(config.shortcuts[1].to_string(), "W");
+
+        // Only changed shortcuts are written, existing entries keep their comments
+        let mut shortcuts = config.shortcuts.clone();
+        shortcuts[0] = Shortcut::parse("Ctrl+1").unwrap();
+        let written = write_shortcuts("show_delta = true\n[shortcuts]\nreset_view = \"Alt+R\" # mine\n", &shortcuts).unwrap();
+        assert_eq!(written, "show_delta = true\n[shortcuts]\nreset_view = \"Alt+R\" # mine\nprobe_mark = \"Ctrl+1\"\n");
+        assert_eq!(write_shortcuts("", &Config::layered("").0.shortcuts).unwrap(), "");
+        let written = write_shortcuts(DEFAULT_CONFIG, &shortcuts).unwrap();
+        assert!(written.contains("probe_mark = \"Ctrl+1\"\r\nhighlighter = \"W\""));
+        assert_eq!(Config::parse(&written).0, Config { shortcuts, ..Config::parse(DEFAULT_CONFIG).0 });
+    }
+
+    #[test]
+    fn test_write_presets() {
+        let (defaults, _) = Config::parse(DEFAULT_CONFIG);
+        let mut presets = defaults.presets.clone();
+        presets.swap(0, 1);
+        presets.remove(2);
+        presets[0].name = "Renamed".into();
+        presets.push(InitialGameSettings { name: "Seeded".into(), seed: Some("abc".into()), ..Default::default() });
+
+        let user_text = "# my presets\nshow_delta = false\n";
+        let written = write_presets(user_text, &presets, Some(1)).unwrap();
+        assert!(written.starts_with("# my presets\nshow_delta = false\n"));
+        let (config, problems) = Config::layered(&written);
+        assert_eq!(problems, vec![]);
+        assert_eq!(config.presets, presets);
+        assert_eq!(config.default_preset, Some(1));
+        assert!(!config.show_delta);
+
+        // Writing it again keeps the text as it is
+        assert_eq!(write_presets(&written, &presets, Some(1)).unwrap(), written);
+
+        // Presets renamed to the same name are kept apart, the default one still points at the right one
+        let last = presets.len() - 1;
+        presets[last].name = presets[1].name.clone();
+        let written = write_presets(&written, &presets, Some(last)).unwrap();
+        let (config, problems) = Config::layered(&written);
+        assert_eq!(problems, vec![]);
+        assert_eq!(config.presets, presets);
+        assert_eq!(config.default_preset, Some(last));
+    }
+}
@@ -64,11 +64,5 @@ mod tests {
         let std_duration = std::time::Duration::new(3661, 534_000_000);
         assert_eq!(&std_duration.hhmmss(), "01:01:01");
         assert_eq!(&std_duration.hhmmssxxx(), "01:01:01.534");
-        let chrono_duration = chrono::Duration::from_std(std_duration).unwrap();
-        assert_eq!(&chrono_duration.hhmmss(), "01:01:01");
-        assert_eq!(&chrono_duration.hhmmssxxx(), "01:01:01.534");
-        let time_duration = time::Duration::from_std(std_duration).unwrap();
-        assert_eq!(&time_duration.hhmmss(), "01:01:01");
-        assert_eq!(&time_duration.hhmmssxxx(), "01:01:01.534");
     }
 }
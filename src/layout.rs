@@ -0,0 +1,495 @@
+use crate::minesweeper_model::DIMENSIONS_COUNT;
+use std::fmt;
+
+pub const AXIS_NAMES: [char; DIMENSIONS_COUNT] = ['x', 'y', 'z', 'u', 'v', 'w'];
+
+// Screen direction of each axis and how the axes are nested.
+// Written as e.g. "xzv/yuw" in config.toml: axes going across, then the ones going down, innermost first.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BoardLayout {
+    pub across: Vec<usize>, // axis indices, innermost first
+    pub down: Vec<usize>,
+}
+
+impl Default for BoardLayout {
+    fn default() -> Self {
+        Self { across: vec![0, 2, 4], down: vec![1, 3, 5] }
+    }
+}
+
+impl BoardLayout {
+    // Every axis has to be used exactly once
+    pub fn parse(text: &str) -> Option<Self> {
+        let (across, down) = text.trim().split_once('/')?;
+        let axes = |part: &str| part.trim().chars()
+                                    .map(|c| AXIS_NAMES.iter().position(|&n| n == c.to_ascii_lowercase()))
+                                    .collect::<Option<Vec<usize>>>();
+        let layout = Self { across: axes(across)?, down: axes(down)? };
+        let mut used = [false; DIMENSIONS_COUNT];
+        for &axis in layout.across.iter().chain(&layout.down) {
+            if std::mem::replace(&mut used[axis], true) {
+                return None;
+            }
+        }
+        used.iter().all(|&u| u).then_some(layout)
+    }
+
+    pub fn transposed(&self) -> Self {
+        Self { across: self.down.clone(), down: self.across.clone() }
+    }
+
+    // Direction (0 across, 1 down) and nesting level of given axis
+    pub fn position(&self, axis: usize) -> (usize, usize) {
+        match self.across.iter().position(|&a| a == axis) {
+            Some(level) => (0, level),
+            None => (1, self.down.iter().position(|&a| a == axis).expect("layout has every axis")),
+        }
+    }
+
+    // Move an axis to the outermost level of given direction
+    pub fn set_direction(&mut self, axis: usize, direction: usize) {
+        self.across.retain(|&a| a != axis);
+        self.down.retain(|&a| a != axis);
+        if direction == 0 {self.across.push(axis)} else {self.down.push(axis)}
+    }
+
+    // Swap an axis with its neighbour one level further in (`outward` false) or out
+    pub fn move_axis(&mut self, axis: usize, outward: bool) {
+        let (direction, level) = self.position(axis);
+        let axes = if direction == 0 {&mut self.across} else {&mut self.down};
+        if outward && level + 1 < axes.len() {
+            axes.swap(level, level + 1);
+        } else if !outward && level > 0 {
+            axes.swap(level, level - 1);
+        }
+    }
+
+    // Layout that shows the board at the biggest zoom inside `available` (logical points).
+    // Only the direction of each axis is chosen, nesting follows the order of the axes.
+    // Of equally good layouts, the one closest to the default is used.
+    pub fn auto(size: [usize; DIMENSIONS_COUNT], tile_spacings: [f32; DIMENSIONS_COUNT], cell_edge: f32,
+                available: [f32; 2]) -> Self {
+        let default = Self::default();
+        let mut best: Option<(f32, usize, Self)> = None;
+        for mask in 0..1u32 << DIMENSIONS_COUNT {
+            let across = (0..DIMENSIONS_COUNT).filter(|&a| mask & (1 << a) != 0);
+            let layout = Self { across: across.collect(),
+                                down: (0..DIMENSIONS_COUNT).filter(|&a| mask & (1 << a) == 0).collect() };
+            // Axes of a single cell go anywhere, there's no point in trying both directions
+            if (0..DIMENSIONS_COUNT).any(|a| size[a] == 1 && layout.position(a).0 != default.position(a).0) {
+                continue;
+            }
+            let extent = BoardGeometry::new(&layout, size, tile_spacings, cell_edge).extent();
+            let zoom = (available[0] / extent[0]).min(available[1] / extent[1]);
+            let changes = (0..DIMENSIONS_COUNT).filter(|&a| layout.position(a).0 != default.position(a).0).count();
+            let better = match &best {
+                None => true,
+                Some((best_zoom, best_changes, _)) => zoom > best_zoom * 1.0001
+                                                     || (zoom >= best_zoom / 1.0001 && changes < *best_changes),
+            };
+            if better {
+                best = Some((zoom, changes, layout));
+            }
+        }
+        best.map_or(default, |(_, _, layout)| layout)
+    }
+}
+
+impl fmt::Display for BoardLayout {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let names = |axes: &[usize]| axes.iter().map(|&a| AXIS_NAMES[a]).collect::<String>();
+        write!(f, "{}/{}", names(&self.across), names(&self.down))
+    }
+}
+
+// 2D or 3D slice of the board shown by the slice explorer, the other axes are fixed
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Slice {
+    pub across: usize,
+    pub down: usize,
+    pub layers: Option<usize>,            // third shown axis, its layers are placed side by side
+    pub fixed: [usize; DIMENSIONS_COUNT], // coordinates of the other axes, the shown ones are ignored
+    pub previews: bool,                   // neighbouring slices along each fixed axis are drawn too
+}
+
+impl Default for Slice {
+    fn default() -> Self {
+        Self { across: 0, down: 1, layers: None, fixed: [0; DIMENSIONS_COUNT], previews: true }
+    }
+}
+
+impl Slice {
+    pub fn shows(&self, axis: usize) -> bool {
+        axis == self.across || axis == self.down || self.layers == Some(axis)
+    }
+
+    // Cell in the middle of the slice
+    pub fn center(&self, size: [usize; DIMENSIONS_COUNT]) -> [usize; DIMENSIONS_COUNT] {
+        std::array::from_fn(|a| if self.shows(a) {size[a] / 2} else {self.fixed[a].min(size[a] - 1)})
+    }
+
+    // Show `axis` across (`slot` 0), down (1) or as layers (2).
+    // An axis that's already shown swaps places with the one it replaces.
+    pub fn show(&mut self, slot: usize, axis: Option<usize>) {
+        let mut slots = [Some(self.across), Some(self.down), self.layers];
+        match slots.iter().position(|&s| s.is_some() && s == axis) {
+            Some(other) => slots.swap(slot, other),
+            None => slots[slot] = axis,
+        }
+        // Across and down can't be left empty
+        if let [Some(across), Some(down), layers] = slots {
+            (self.across, self.down, self.layers) = (across, down, layers);
+        }
+    }
+}
+
+// Nesting level of the board along one screen direction
+#[derive(Clone, Debug)]
+struct Level {
+    axis: usize,
+    size: usize,
+    block: f32,  // extent of one element of this level (a cell, or a block of the level inside)
+    period: f32, // distance between the starts of two elements
+}
+
+// Part of the board drawn at one place, either the whole board or a single slice of it
+#[derive(Clone, Debug)]
+pub struct Panel {
+    pub origin: [f32; 2],
+    pub fixed: [Option<usize>; DIMENSIONS_COUNT], // coordinates of the axes that aren't shown
+    pub faded: bool,                              // neighbouring slice, drawn as a preview
+    pub label: String,                            // drawn above the panel
+}
+
+// Positions of cells in logical points, before the view is zoomed and panned.
+// Gaps between nested blocks come from `tile_spacings`, in pairs for the across and down direction
+// from the innermost level outwards. Levels further out keep growing by the last step.
+// Axes of a single cell take no space, so they don't use up a gap size either.
+#[derive(Clone, Debug)]
+pub struct BoardGeometry {
+    cell_edge: f32,
+    size: [usize; DIMENSIONS_COUNT],
+    levels: [Vec<Level>; 2], // across and down, innermost first
+    panel_extent: [f32; 2],
+    panels: Vec<Panel>,      // the current slice comes first
+    extent: [f32; 2],
+}
+
+impl BoardGeometry {
+    pub fn new(layout: &BoardLayout, size: [usize; DIMENSIONS_COUNT], tile_spacings: [f32; DIMENSIONS_COUNT],
+               cell_edge: f32) -> Self {
+        let mut levels = [vec![], vec![]];
+        let mut extent = [cell_edge; 2];
+        for (direction, axes) in [&layout.across, &layout.down].into_iter().enumerate() {
+            for &axis in axes.iter().filter(|&&a| size[a] > 1) {
+                let level = levels[direction].len();
+                let spacing = |l: usize| tile_spacings[2*l + direction];
+                let gap = if level < 3 {spacing(level)} else {spacing(2) + (level - 2) as f32 * (spacing(2) - spacing(1))};
+                let block = extent[direction];
+                levels[direction].push(Level { axis, size: size[axis], block, period: block + gap.max(0.0) });
+                extent[direction] = size[axis] as f32 * block + (size[axis] - 1) as f32 * gap.max(0.0);
+            }
+        }
+        let panels = vec![Panel { origin: [0.0, 0.0], fixed: [None; DIMENSIONS_COUNT], faded: false, label: "".into() }];
+        Self { cell_edge, size, levels, panel_extent: extent, panels, extent }
+    }
+
+    // Given slice with the neighbouring ones beside it: the slices before on the left, the ones after
+    // on the right, a row for each fixed axis. Labels above the slices say which one they are.
+    pub fn slice(slice: &Slice, size: [usize; DIMENSIONS_COUNT], wrap: [bool; DIMENSIONS_COUNT],
+                 tile_spacings: [f32; DIMENSIONS_COUNT], cell_edge: f32) -> Self {
+        let layout = BoardLayout { across: [slice.across].into_iter().chain(slice.layers).collect(),
+                                   down: vec![slice.down] };
+        let shown_size = std::array::from_fn(|a| if slice.shows(a) {size[a]} else {1});
+        let mut geometry = Self::new(&layout, shown_size, tile_spacings, cell_edge);
+        let [width, height] = geometry.panel_extent;
+        let gap = 2.0 * cell_edge;
+        
+        let fixed: [Option<usize>; DIMENSIONS_COUNT]
+            = std::array::from_fn(|a| (!slice.shows(a)).then(|| slice.fixed[a].min(size[a] - 1)));
+        let mut previews = vec![];
+        let fixed_axes = (0..DIMENSIONS_COUNT).filter(|&a| fixed[a].is_some() && size[a] > 1);
+        for (row, axis) in fixed_axes.clone().filter(|_| slice.previews).enumerate() {
+            let c = fixed[axis].unwrap_or_default();
+            let before = if c > 0 {Some(c - 1)} else if wrap[axis] {Some(size[axis] - 1)} else {None};
+            let after = if c + 1 < size[axis] {Some(c + 1)} else if wrap[axis] {Some(0)} else {None};
+            // With two cells the wrapped slice before is the one after
+            for (column, neighbour) in [(0.0, before.filter(|&b| Some(b) != after)), (2.0, after)] {
+                if let Some(n) = neighbour {
+                    let mut preview_fixed = fixed;
+                    preview_fixed[axis] = Some(n);
+                    previews.push(Panel { origin: [column * (width + gap), gap + row as f32 * (height + gap)],
+                                          fixed: preview_fixed, faded: true,
+                                          label: format!("{} = {}", AXIS_NAMES[axis], n) });
+                }
+            }
+        }
+        let label = fixed_axes.map(|a| format!("{} = {}", AXIS_NAMES[a], fixed[a].unwrap_or_default()))
+                              .collect::<Vec<_>>().join(", ");
+        let current = Panel { origin: [if previews.is_empty() {0.0} else {width + gap}, gap],
+                              fixed, faded: false, label };
+        
+        geometry.panels = [current].into_iter().chain(previews).collect();
+        geometry.size = size;
+        geometry.extent = [0, 1].map(|d| geometry.panels.iter().map(|p| p.origin[d]).fold(0.0, f32::max)
+                                         + geometry.panel_extent[d]);
+        geometry
+    }
+
+    // Width and height of everything drawn
+    pub fn extent(&self) -> [f32; 2] {
+        self.extent
+    }
+
+    pub fn panels(&self) -> &[Panel] {
+        &self.panels
+    }
+
+    // Upper left corner of given cell inside its panel
+    fn panel_offset(&self, coords: [usize; DIMENSIONS_COUNT]) -> [f32; 2] {
+        self.levels.each_ref().map(|levels| levels.iter().map(|l| coords[l.axis] as f32 * l.period).sum())
+    }
+
+    // Upper left corners of given cell, a cell may be drawn in more than one panel (or in none)
+    pub fn cell_origins(&self, coords: [usize; DIMENSIONS_COUNT]) -> impl Iterator<Item = [f32; 2]> + '_ {
+        let [dx, dy] = self.panel_offset(coords);
+        self.panels.iter()
+            .filter(move |p| (0..DIMENSIONS_COUNT).all(|a| p.fixed[a].is_none_or(|f| f == coords[a])))
+            .map(move |p| [p.origin[0] + dx, p.origin[1] + dy])
+    }
+
+    // Upper left corner of given cell, in the current slice if it's there
+    pub fn cell_origin(&self, coords: [usize; DIMENSIONS_COUNT]) -> Option<[f32; 2]> {
+        self.cell_origins(coords).next()
+    }
+
+    // Every drawn cell with its upper left corner and whether it belongs to a preview
+    pub fn cells(&self) -> impl Iterator<Item = ([usize; DIMENSIONS_COUNT], [f32; 2], bool)> + '_ {
+        self.panels.iter().flat_map(move |panel| {
+            let ranges: [(usize, usize); DIMENSIONS_COUNT]
+                = std::array::from_fn(|a| panel.fixed[a].map_or((0, self.size[a]), |f| (f, 1)));
+            let count: usize = ranges.iter().map(|&(_, len)| len).product();
+            (0..count).map(move |mut index| {
+                // x changes fastest
+                let coords = ranges.map(|(start, len)| {
+                    let c = start + index % len;
+                    index /= len;
+                    c
+                });
+                let [dx, dy] = self.panel_offset(coords);
+                (coords, [panel.origin[0] + dx, panel.origin[1] + dy], panel.faded)
+            })
+        })
+    }
+
+    // Drawn cells overlapping the rectangle between `min` and `max`, without going through the others.
+    // Cells come with their upper left corner and whether they belong to a preview, as in `cells`.
+    pub fn cells_in(&self, min: [f32; 2], max: [f32; 2])
+            -> impl Iterator<Item = ([usize; DIMENSIONS_COUNT], [f32; 2], bool)> + '_ {
+        self.elements_in(min, max, 0)
+    }
+
+    // Same as `cells_in` for the innermost 2D blocks, the coordinates of the axes inside them are zero
+    pub fn blocks_in(&self, min: [f32; 2], max: [f32; 2])
+            -> impl Iterator<Item = ([usize; DIMENSIONS_COUNT], [f32; 2], bool)> + '_ {
+        self.elements_in(min, max, 1)
+    }
+
+    // Width and height of the innermost 2D blocks
+    pub fn block_extent(&self) -> [f32; 2] {
+        [0, 1].map(|d| self.element_extent(d, 1))
+    }
+
+    // Axes inside the innermost 2D blocks, across and down (none if the block is a single cell wide)
+    pub fn block_axes(&self) -> [Option<usize>; 2] {
+        self.levels.each_ref().map(|levels| levels.first().map(|l| l.axis))
+    }
+
+    // Extent of the elements left after leaving out given number of the innermost levels
+    fn element_extent(&self, direction: usize, inner_levels: usize) -> f32 {
+        self.levels[direction].get(inner_levels).map_or(self.panel_extent[direction], |l| l.block)
+    }
+
+    fn elements_in(&self, min: [f32; 2], max: [f32; 2], inner_levels: usize)
+            -> impl Iterator<Item = ([usize; DIMENSIONS_COUNT], [f32; 2], bool)> + '_ {
+        self.panels.iter().flat_map(move |panel| {
+            let [columns, rows] = [0, 1].map(|d| self.visible_lines(d, [min[d] - panel.origin[d],
+                                                                       max[d] - panel.origin[d]], inner_levels));
+            rows.into_iter().flat_map(move |(row, dy)| columns.clone().into_iter().map(move |(column, dx)| {
+                // Each direction only sets its own axes, the others are zero
+                let coords = std::array::from_fn(|a| panel.fixed[a].unwrap_or(row[a] + column[a]));
+                (coords, [panel.origin[0] + dx, panel.origin[1] + dy], panel.faded)
+            }))
+        })
+    }
+
+    // Columns (or rows) of cells of a panel overlapping given range in one direction, with their offset.
+    // Inverse of `cell_in_panel`: going outermost first, only elements overlapping the range are split further.
+    // Leaving out inner levels gives the columns of blocks instead.
+    fn visible_lines(&self, direction: usize, range: [f32; 2], inner_levels: usize)
+            -> Vec<([usize; DIMENSIONS_COUNT], f32)> {
+        let mut lines = vec![([0; DIMENSIONS_COUNT], 0.0)];
+        for level in self.levels[direction].iter().skip(inner_levels).rev() {
+            lines = lines.into_iter().flat_map(|(coords, start)| {
+                let first = ((range[0] - start - level.block) / level.period).ceil().max(0.0) as usize;
+                let end = if range[1] < start {0} else {((range[1] - start) / level.period) as usize + 1};
+                (first..end.min(level.size)).map(move |index| {
+                    let mut coords = coords;
+                    coords[level.axis] = index;
+                    (coords, start + index as f32 * level.period)
+                })
+            }).collect();
+        }
+        let extent = self.element_extent(direction, inner_levels);
+        lines.retain(|&(_, start)| start <= range[1] && start + extent >= range[0]);
+        lines
+    }
+
+    // Cell under given point, if it's not in a gap between cells
+    pub fn cell_at(&self, point: [f32; 2]) -> Option<[usize; DIMENSIONS_COUNT]> {
+        self.panels.iter().find_map(|panel| {
+            let mut coords = self.cell_in_panel([point[0] - panel.origin[0], point[1] - panel.origin[1]])?;
+            for (c, f) in coords.iter_mut().zip(panel.fixed) {
+                *c = f.unwrap_or(*c);
+            }
+            Some(coords)
+        })
+    }
+
+    // Uses modular cutoff to decide in constant time whether the point is over any cell:
+    // the offset inside the block of each level is either inside the block of the level below or in a gap
+    fn cell_in_panel(&self, point: [f32; 2]) -> Option<[usize; DIMENSIONS_COUNT]> {
+        let mut coords = [0; DIMENSIONS_COUNT];
+        for (direction, levels) in self.levels.iter().enumerate() {
+            let mut offset = point[direction];
+            if offset < 0.0 {
+                return None;
+            }
+            // Outermost first, whatever remains is the offset inside the block
+            for level in levels.iter().rev() {
+                let index = (offset / level.period) as usize;
+                if index >= level.size {
+                    return None;
+                }
+                offset -= index as f32 * level.period;
+                if offset > level.block {
+                    return None;
+                }
+                coords[level.axis] = index;
+            }
+            if offset > self.cell_edge {
+                return None;
+            }
+        }
+        Some(coords)
+    }
+
+    // Whether a side of given cell is the edge of the innermost block in given direction,
+    // the first side when `end` is false, the last one otherwise
+    pub fn is_block_edge(&self, coords: [usize; DIMENSIONS_COUNT], direction: usize, end: bool) -> bool {
+        match self.levels[direction].first() {
+            Some(level) => coords[level.axis] == if end {level.size - 1} else {0},
+            None => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(BoardLayout::parse("xzv/yuw"), Some(BoardLayout::default()));
+        assert_eq!(BoardLayout::default().to_string(), "xzv/yuw");
+        assert_eq!(BoardLayout::parse(" xyzuvw/ ").map(|l| l.down), Some(vec![]));
+        assert_eq!(BoardLayout::parse("xzv/yu"), None);
+        assert_eq!(BoardLayout::parse("xzv/yuwx"), None);
+        assert_eq!(BoardLayout::parse("xzvyuw"), None);
+    }
+
+    #[test]
+    fn test_geometry() {
+        let spacings = [0.0, 0.0, 10.0, 10.0, 20.0, 20.0];
+        let size = [2, 3, 4, 1, 2, 1];
+        let geometry = BoardGeometry::new(&BoardLayout::default(), size, spacings, 30.0);
+        // Blocks of x are 60 wide, blocks of z 4*60 + 3*10
+        assert_eq!(geometry.extent(), [2.0*270.0 + 20.0, 90.0]);
+        assert_eq!(geometry.cell_origin([1, 2, 3, 0, 1, 0]), Some([30.0 + 3.0*70.0 + 290.0, 60.0]));
+        assert_eq!(geometry.cells().count(), 2*3*4*2);
+        assert_eq!(geometry.cell_at([30.0 + 3.0*70.0 + 290.0 + 5.0, 65.0]), Some([1, 2, 3, 0, 1, 0]));
+        assert_eq!(geometry.cell_at([65.0, 5.0]), None);
+        assert_eq!(geometry.cell_at([275.0, 5.0]), None);
+        assert_eq!(geometry.cell_at([5.0, 95.0]), None);
+        // Only x = 1 of the last z block and the first y row
+        let visible: Vec<_> = geometry.cells_in([30.0 + 3.0*70.0 + 290.0 + 5.0, 5.0], [2000.0, 20.0])
+                                      .map(|(coords, ..)| coords).collect();
+        assert_eq!(visible, vec![[1, 0, 3, 0, 1, 0]]);
+        assert_eq!(geometry.cells_in([-10.0, -10.0], [2000.0, 2000.0]).count(), geometry.cells().count());
+        assert_eq!(geometry.cells_in([61.0, 0.0], [69.0, 90.0]).count(), 0);
+        // Blocks of x and y
+        assert_eq!(geometry.block_extent(), [60.0, 90.0]);
+        assert_eq!(geometry.block_axes(), [Some(0), Some(1)]);
+        let visible: Vec<_> = geometry.blocks_in([275.0, 0.0], [2000.0, 1.0]).collect();
+        assert_eq!(visible, vec![([0, 0, 0, 0, 1, 0], [290.0, 0.0], false), ([0, 0, 1, 0, 1, 0], [360.0, 0.0], false),
+                                 ([0, 0, 2, 0, 1, 0], [430.0, 0.0], false), ([0, 0, 3, 0, 1, 0], [500.0, 0.0], false)]);
+        assert!(geometry.is_block_edge([1, 0, 0, 0, 0, 0], 0, true));
+        assert!(!geometry.is_block_edge([1, 1, 0, 0, 0, 0], 1, false));
+
+        // Axes of a single cell don't take the gap of the innermost level
+        let layout = BoardLayout::parse("ux/yzvw").unwrap();
+        let geometry = BoardGeometry::new(&layout, size, spacings, 30.0);
+        assert_eq!(geometry.extent(), [60.0, 2.0*(4.0*90.0 + 3.0*10.0) + 20.0]);
+    }
+
+    #[test]
+    fn test_slice() {
+        let spacings = [0.0, 0.0, 10.0, 10.0, 20.0, 20.0];
+        let size = [3, 3, 3, 2, 1, 3];
+        let wrap = [false, false, false, true, false, true];
+        let slice = Slice { fixed: [0, 0, 0, 1, 0, 2], ..Default::default() };
+        let geometry = BoardGeometry::slice(&slice, size, wrap, spacings, 30.0);
+        // z has no slice before it, u only one other slice, w wraps around
+        let labels: Vec<_> = geometry.panels().iter().map(|p| p.label.as_str()).collect();
+        assert_eq!(labels, vec!["z = 0, u = 1, w = 2", "z = 1", "u = 0", "w = 1", "w = 0"]);
+        assert_eq!(geometry.extent(), [3.0*90.0 + 2.0*60.0, 3.0*(90.0 + 60.0)]);
+        assert_eq!(geometry.cells().count(), 5*9);
+        assert_eq!(geometry.cells().filter(|&(_, _, faded)| faded).count(), 4*9);
+
+        // Points map to the cells of the panel they are in
+        assert_eq!(geometry.cell_origin([2, 1, 0, 1, 0, 2]), Some([150.0 + 60.0, 60.0 + 30.0]));
+        assert_eq!(geometry.cell_at([150.0 + 65.0, 60.0 + 35.0]), Some([2, 1, 0, 1, 0, 2]));
+        assert_eq!(geometry.cell_at([300.0 + 5.0, 60.0 + 5.0]), Some([0, 0, 1, 1, 0, 2]));
+        assert_eq!(geometry.cell_at([5.0, 60.0 + 2.0*150.0 + 35.0]), Some([0, 1, 0, 1, 0, 1]));
+        assert_eq!(geometry.cell_at([150.0 + 5.0, 150.0 + 65.0]), None);
+        assert_eq!(geometry.cell_origin([0, 0, 2, 1, 0, 2]), None);
+        // Parts of the current slice and of the one after along z
+        let visible: Vec<_> = geometry.cells_in([150.0 + 65.0, 60.0 + 65.0], [300.0 + 5.0, 60.0 + 65.0]).collect();
+        assert_eq!(visible, vec![([2, 2, 0, 1, 0, 2], [150.0 + 60.0, 60.0 + 60.0], false),
+                                 ([0, 2, 1, 1, 0, 2], [300.0, 60.0 + 60.0], true)]);
+
+        let mut slice = slice;
+        slice.show(0, Some(1));
+        assert_eq!((slice.across, slice.down), (1, 0));
+        slice.show(2, Some(2));
+        slice.show(2, Some(1));
+        assert_eq!((slice.across, slice.down, slice.layers), (2, 0, Some(1)));
+        slice.show(1, Some(4));
+        slice.show(2, None);
+        assert_eq!((slice.across, slice.down, slice.layers), (2, 4, None));
+        // Only layers can be hidden
+        slice.show(0, None);
+        assert_eq!((slice.across, slice.down), (2, 4));
+    }
+
+    #[test]
+    fn test_auto() {
+        let spacings = [0.0, 0.0, 10.0, 10.0, 20.0, 20.0];
+        let wide = [1600.0, 900.0];
+        assert_eq!(BoardLayout::auto([3; 6], spacings, 30.0, wide), BoardLayout::default());
+        assert_eq!(BoardLayout::auto([30, 16, 1, 1, 1, 1], spacings, 30.0, wide), BoardLayout::default());
+        assert_eq!(BoardLayout::auto([30, 16, 1, 1, 1, 1], spacings, 30.0, [900.0, 1600.0]).to_string(), "yzv/xuw");
+        assert_eq!(BoardLayout::auto([16, 16, 16, 1, 1, 1], spacings, 30.0, wide).to_string(), "xzv/yuw");
+        assert_eq!(BoardLayout::auto([4, 4, 4, 4, 4, 1], spacings, 30.0, [1000.0, 1000.0]).to_string(), "xzv/yuw");
+    }
+}
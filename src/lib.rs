@@ -0,0 +1,13 @@
+// Game model shared by all front-ends (egui, terminal) and tools
+#![feature(step_trait)]
+
+pub mod bwi;
+pub mod config;
+pub mod hhmmss;
+pub mod layout;
+pub mod minesweeper_model;
+pub mod protocol;
+pub mod solver;
+pub mod statistics;
+
+pub use minesweeper_model::{CellState, DIMENSIONS_COUNT, GameBoard, GameState, InitialGameSettings};
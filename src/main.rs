@@ -1,35 +1,32 @@
 // hide console window on Windows in release (also disables console output)
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
-#![feature(step_trait)]
 
 extern crate eframe;
 extern crate itertools;
-extern crate toml;
 extern crate rand_chacha;
 
 use itertools::Itertools;
 
-pub mod hhmmss;
-use hhmmss::Hhmmss;
+use minesweeper6d::hhmmss::Hhmmss;
+use minesweeper6d::layout::{AXIS_NAMES, BoardGeometry, BoardLayout, Slice};
+use minesweeper6d::bwi::BWI;
+use minesweeper6d::config::{self, Config, ConfigProblem, DEFAULT_CONFIG, Preferences, Severity,
+                            SHORTCUT_ACTIONS, SHORTCUT_COUNT};
+use minesweeper6d::minesweeper_model::{CellQuery, CellState, DIMENSIONS_COUNT, GameBoard, GameState,
+                                       HighlightOperation, InitialGameSettings, NeighborCounts};
+use minesweeper6d::solver::{LossAnalysis, ProbeVerdict};
+use minesweeper6d::statistics::{HighScore, ReplayAction, ReplayActionKind, Statistics};
 
-pub mod bwi;
-use bwi::BWI;
-
-pub mod minesweeper_model;
-use minesweeper_model::{CellState, DIMENSIONS_COUNT, GameBoard, GameState, InitialGameSettings};
-
-use std::{cmp::min, time::Duration};
+use std::path::PathBuf;
+use std::time::Duration;
 #[cfg(not(target_arch = "wasm32"))]
 use std::fs;
-#[cfg(target_arch = "wasm32")]
-use std::include_str;
 
 use eframe::{egui, emath::Align2};
 use eframe::egui::{Button, containers::panel::TopBottomPanel, Key, KeyboardShortcut, 
-                   menu, Modifiers, PointerButton, Response, RichText, Sense};
-use eframe::epaint::{Color32, FontId, Pos2, Rect, Rounding, Shadow, Shape, Stroke};
+                   menu, Modifiers, PointerButton, Response, RichText, Sense, TextureOptions};
+use eframe::epaint::{Color32, ColorImage, FontId, Pos2, Rect, Rounding, Shadow, Shape, Stroke, TextureHandle};
 use web_time::SystemTime;
-use toml::Table;
 
 #[derive(PartialEq)]
 enum CursorMode {
@@ -37,40 +34,191 @@ enum CursorMode {
     Highlighter,
 }
 
+// Bindings of the actions in `config::SHORTCUT_ACTIONS`, indexed by the constants below
 pub struct Shortcuts {
-    probe_mark_shortcut: KeyboardShortcut,
-    highlighter_shortcut: KeyboardShortcut,
-    highlight_group_shortcuts: [KeyboardShortcut; 8],
-    
-    reset_view_shortcut: KeyboardShortcut,
-    zoom_to_fit_shortcut: KeyboardShortcut,
+    bindings: [KeyboardShortcut; SHORTCUT_COUNT],
+}
+
+// Built-in bindings
+impl Default for Shortcuts {
+    fn default() -> Self {
+        Self::from_config(&Config::default().shortcuts).0
+    }
 }
 
 impl Shortcuts {
-    pub fn new() -> Self {
-        let mod_none = Modifiers{
-            alt: false,
-            ctrl: false,
-            shift: false,
-            mac_cmd: false,
-            command: false,
+    const PROBE_MARK: usize = 0;
+    const HIGHLIGHTER: usize = 1;
+    const HIGHLIGHT_GROUP_1: usize = 2; // followed by the other 7 groups
+    const RESET_VIEW: usize = 10;
+    const ZOOM_TO_FIT: usize = 11;
+    const TRANSPOSE_LAYOUT: usize = 12;
+    const AUTO_LAYOUT: usize = 13;
+    const SLICE_VIEW: usize = 14;
+    const MINIMAP: usize = 15;
+    const GO_TO: usize = 16;
+    const FIND_OPEN_NUMBER: usize = 17;
+    const FIND_SAFE_CELL: usize = 18;
+    const HIGHLIGHT_NEIGHBORS: usize = 19;
+    const CURSOR_MOVE: usize = 20; // minus and plus for each axis, x first
+    const CURSOR_PROBE: usize = 32;
+    const CURSOR_MARK: usize = 33;
+    const CURSOR_CHORD: usize = 34;
+    const CURSOR_HIGHLIGHT: usize = 35;
+
+    // Unknown keys keep their built-in binding, bindings used twice are reported
+    fn from_config(shortcuts: &[config::Shortcut; SHORTCUT_COUNT]) -> (Self, Vec<ConfigProblem>) {
+        let defaults = Config::default().shortcuts;
+        let mut problems = vec![];
+        let bindings = std::array::from_fn(|ii| {
+            to_keyboard_shortcut(&shortcuts[ii]).unwrap_or_else(|| {
+                problems.push(ConfigProblem {
+                    severity: Severity::Error,
+                    line: None,
+                    key: format!("shortcuts.{}", SHORTCUT_ACTIONS[ii].0),
+                    message: format!("unknown key `{}`, {} is used", shortcuts[ii].key, defaults[ii]),
+                });
+                to_keyboard_shortcut(&defaults[ii]).expect("built-in shortcuts are valid")
+            })
+        });
+        let ret = Self { bindings };
+        for (ii, (action, _)) in SHORTCUT_ACTIONS.iter().enumerate() {
+            if let Some(jj) = ret.conflict(ii, &ret.bindings[ii]).filter(|&jj| jj > ii) {
+                problems.push(ConfigProblem {
+                    severity: Severity::Warning,
+                    line: None,
+                    key: format!("shortcuts.{}", SHORTCUT_ACTIONS[jj].0),
+                    message: format!("same as `shortcuts.{}`, only one of them works", action),
+                });
+            }
+        }
+        (ret, problems)
+    }
+
+    // Other action already bound to given shortcut
+    fn conflict(&self, action: usize, shortcut: &KeyboardShortcut) -> Option<usize> {
+        (0..SHORTCUT_COUNT).find(|&ii| ii != action && self.bindings[ii] == *shortcut)
+    }
+
+    // Actions whose shortcut was pressed. Shortcuts with more modifiers are checked first,
+    // so that pressing Shift+Q doesn't trigger an action bound to plain Q.
+    fn consume_pressed(&self, ctx: &egui::Context) -> Vec<usize> {
+        let modifier_count = |m: Modifiers| [m.alt, m.ctrl, m.shift, m.command].iter().filter(|&&b| b).count();
+        let mut order: Vec<usize> = (0..SHORTCUT_COUNT).collect();
+        order.sort_by_key(|&ii| std::cmp::Reverse(modifier_count(self.bindings[ii].modifiers)));
+        ctx.input_mut(|i| order.into_iter().filter(|&ii| i.consume_shortcut(&self.bindings[ii])).collect())
+    }
+}
+
+fn to_keyboard_shortcut(shortcut: &config::Shortcut) -> Option<KeyboardShortcut> {
+    let modifiers = Modifiers {
+        alt: shortcut.alt,
+        ctrl: shortcut.ctrl,
+        shift: shortcut.shift,
+        mac_cmd: false,
+        command: shortcut.command,
+    };
+    Key::from_name(&shortcut.key).map(|key| KeyboardShortcut::new(modifiers, key))
+}
+
+// Pressed keys have both `ctrl` and `command` set on Windows and Linux, only ⌘ sets `mac_cmd`
+fn from_keyboard_shortcut(shortcut: &KeyboardShortcut) -> config::Shortcut {
+    config::Shortcut {
+        ctrl: shortcut.modifiers.ctrl,
+        command: shortcut.modifiers.mac_cmd || (shortcut.modifiers.command && !shortcut.modifiers.ctrl),
+        alt: shortcut.modifiers.alt,
+        shift: shortcut.modifiers.shift,
+        key: shortcut.logical_key.name().into(),
+    }
+}
+
+// Same format as in config.toml, alpha is only written when the colour is translucent
+fn color_to_hex(color: Color32) -> String {
+    if color.is_opaque() {
+        format!("#{:02X}{:02X}{:02X}", color.r(), color.g(), color.b())
+    } else {
+        format!("#{:02X}{:02X}{:02X}{:02X}", color.r(), color.g(), color.b(), color.a())
+    }
+}
+
+fn format_coords(coords: [usize; DIMENSIONS_COUNT]) -> String {
+    format!("[{}]", coords.iter().join(", "))
+}
+
+// Coordinates separated by commas or spaces (brackets are fine too), missing ones at the end are zero
+fn parse_coords(text: &str, size: [usize; DIMENSIONS_COUNT]) -> Result<[usize; DIMENSIONS_COUNT], String> {
+    let parts: Vec<&str> = text.split(|c: char| c == ',' || c == '[' || c == ']' || c.is_whitespace())
+                               .filter(|p| !p.is_empty()).collect();
+    if parts.len() > DIMENSIONS_COUNT {
+        return Err(format!("at most {} coordinates", DIMENSIONS_COUNT));
+    }
+    let mut coords = [0; DIMENSIONS_COUNT];
+    for (axis, part) in parts.into_iter().enumerate() {
+        coords[axis] = match part.parse::<usize>() {
+            Ok(c) if c < size[axis] => c,
+            _ => return Err(format!("{} has to be a number from 0 to {}", AXIS_NAMES[axis], size[axis] - 1)),
         };
-        Self {
-            probe_mark_shortcut: KeyboardShortcut::new(mod_none, Key::Q),
-            highlighter_shortcut: KeyboardShortcut::new(mod_none, Key::W),
-            highlight_group_shortcuts: [KeyboardShortcut::new(mod_none, Key::Num1),
-                                        KeyboardShortcut::new(mod_none, Key::Num2),
-                                        KeyboardShortcut::new(mod_none, Key::Num3),
-                                        KeyboardShortcut::new(mod_none, Key::Num4),
-                                        KeyboardShortcut::new(mod_none, Key::Num5),
-                                        KeyboardShortcut::new(mod_none, Key::Num6),
-                                        KeyboardShortcut::new(mod_none, Key::Num7),
-                                        KeyboardShortcut::new(mod_none, Key::Num8)],
-            
-            reset_view_shortcut: KeyboardShortcut::new(mod_none, Key::D),
-            zoom_to_fit_shortcut: KeyboardShortcut::new(mod_none, Key::F),
+    }
+    Ok(coords)
+}
+
+// Like `Painter::text`, but returns the shape instead of adding it
+fn text_shape(painter: &egui::Painter, pos: Pos2, anchor: Align2, text: impl ToString, font_id: FontId,
+              color: Color32) -> Shape {
+    let galley = painter.layout_no_wrap(text.to_string(), font_id, color);
+    Shape::galley(anchor.anchor_size(pos, galley.size()).min, galley, color)
+}
+
+// User's configuration, layered over the built-in one. Preferences changed in the UI are written back to it.
+struct UserConfig {
+    path: Option<PathBuf>, // None on the web, where the text is kept in browser storage instead
+    text: String,          // empty if the file doesn't exist yet
+    problem: Option<ConfigProblem>,
+}
+
+// How long a finger has to stay on a cell to mark it
+const LONG_PRESS_SECONDS: f64 = 0.5;
+
+// Neighbouring slices in the slice explorer are drawn with faded colours
+const PREVIEW_OPACITY: f32 = 0.35;
+
+// Zoomed out further, the innermost blocks are drawn as summary tiles instead of single cells
+const SUMMARY_ZOOM: f32 = 0.10;
+
+// Longer side of the minimap, in points
+const MINIMAP_SIZE: f32 = 200.0;
+
+// Key under which the web build keeps the user's configuration (in localStorage)
+#[cfg(target_arch = "wasm32")]
+const CONFIG_STORAGE_KEY: &str = "config";
+
+// File given by `--config <path>`, otherwise config.toml in the working directory (if there is one),
+// otherwise config.toml in the platform config directory (e.g. ~/.config/minesweeper6d/)
+#[cfg(not(target_arch = "wasm32"))]
+fn load_user_config() -> UserConfig {
+    let mut path = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--config" {
+            path = args.next().map(PathBuf::from);
+        } else if let Some(value) = arg.strip_prefix("--config=") {
+            path = Some(PathBuf::from(value));
         }
     }
+    let path = config::config_file_path(path);
+
+    // A missing file is fine, it is created once preferences are saved
+    let (text, problem) = match &path {
+        Some(p) if p.exists() => match fs::read_to_string(p) {
+            Ok(text) => (text, None),
+            Err(e) => ("".into(), Some(ConfigProblem {
+                severity: Severity::Error, line: None, key: "".into(),
+                message: format!("unable to read the file: {}", e)
+            })),
+        },
+        _ => ("".into(), None),
+    };
+    UserConfig { path, text, problem }
 }
 
 // When compiling natively:
@@ -79,7 +227,7 @@ fn main() -> Result<(), eframe::Error> {
     let options = eframe::NativeOptions {
         ..Default::default()
     };
-    let config_content = fs::read_to_string("config.toml").unwrap_or_else(|_| "".into());
+    let user_config = load_user_config();
 
     eframe::run_native(
         "Minesweeper6D",
@@ -91,7 +239,7 @@ fn main() -> Result<(), eframe::Error> {
                 style.visuals.window_rounding = Rounding::ZERO;
                 style.visuals.window_shadow = Shadow::NONE;
             });
-            Box::new(MinesweeperViewController::new(config_content))
+            Box::new(MinesweeperViewController::new(user_config, cc.storage))
         }),
     )
 }
@@ -103,7 +251,6 @@ fn main() {
     eframe::WebLogger::init(log::LevelFilter::Debug).ok();
 
     let web_options = eframe::WebOptions::default();
-    let config_content = include_str!("../config.toml").to_owned();
     
     wasm_bindgen_futures::spawn_local(async {
         eframe::WebRunner::new()
@@ -117,7 +264,11 @@ fn main() {
                         style.visuals.window_rounding = Rounding::ZERO;
                         style.visuals.window_shadow = Shadow::NONE;
                     });
-                    Box::new(MinesweeperViewController::new(config_content))
+                    // First visit starts with a copy of the built-in configuration
+                    let text = cc.storage.and_then(|s| s.get_string(CONFIG_STORAGE_KEY))
+                                         .unwrap_or_else(|| DEFAULT_CONFIG.into());
+                    let user_config = UserConfig { path: None, text, problem: None };
+                    Box::new(MinesweeperViewController::new(user_config, cc.storage))
                 }),
             )
             .await
@@ -125,24 +276,65 @@ fn main() {
     });
 }
 
+// Replay being played back on the board, time is measured from `start_time`
+struct ReplayPlayback {
+    actions: Vec<ReplayAction>,
+    next: usize,
+}
+
+// What the cached board shapes were built from, they are rebuilt when any of it changes
+// (or when the view moves outside the area they cover)
+#[derive(PartialEq)]
+struct BoardCacheKey {
+    revision: Option<u64>,
+    zoom_factor: f32,
+    pixels_per_point: f32,
+    cell_edge: f32,
+    tile_spacings: [f32; DIMENSIONS_COUNT],
+    settings: ([usize; DIMENSIONS_COUNT], [bool; DIMENSIONS_COUNT]),
+    layout: BoardLayout,
+    slice: Option<Slice>,
+    show_delta: bool,
+    highlight_colors: [Color32; 8],
+}
+
 struct MinesweeperViewController {
     current_initial_settings: InitialGameSettings,
     next_initial_settings: InitialGameSettings,
 
     next_selected_preset: Option<u32>,
     presets: Vec<InitialGameSettings>,
+    default_preset: Option<usize>,
+    new_preset_name: String,
 
     game: Option<GameBoard>,
     start_time: Option<SystemTime>,
     end_time: Option<SystemTime>,
+    post_mortem: Option<LossAnalysis>,
+    left_clicks: u32,
+    right_clicks: u32,
+    chord_clicks: u32,
+    assists_used: bool,
+    replay: Vec<ReplayAction>,
+    replay_playback: Option<ReplayPlayback>,
+    
+    statistics: Statistics,
+    statistics_dirty: bool,
+    preferences_dirty: bool,
+    presets_dirty: bool,
     
     cursor_mode: CursorMode,
     selected_highlighters: u8,
+    highlight_operands: [usize; 3], // groups of the set operations: first, second and target, from 1
     
     view_origin: Pos2,
     zoom_factor: f32,
     cell_edge: f32,
     tile_spacings: [f32; DIMENSIONS_COUNT],
+    layout: BoardLayout,
+    auto_layout: bool,
+    slice: Slice,
+    slice_view: bool, // only a slice of the board is shown, its window is open
     
     show_timer_miliseconds: bool,
     show_delta: bool,
@@ -150,22 +342,52 @@ struct MinesweeperViewController {
     unlimited_zoom: bool,
     probe_marked: bool,
     neighbor_coords: Option<[usize; DIMENSIONS_COUNT]>,
+    go_to_text: String,
+    go_to_error: Option<String>,
+    find_status: Option<&'static str>, // "find next" found nothing, shown in the bottom panel
+    cursor_coords: Option<[usize; DIMENSIONS_COUNT]>, // keyboard cursor, shown once it's first moved
+    touch_seen: bool,          // on-screen Probe/Flag toggle is shown once the board was touched
+    touch_press_handled: bool, // current touch was a long press or a gesture, not a tap
+    flag_mode: bool,           // taps mark and long presses probe
     
     new_game_window_enabled: bool,
+    presets_window_enabled: bool,
+    layout_window_enabled: bool,
+    go_to_window_enabled: bool,
     rules_window_enabled: bool,
     controls_window_enabled: bool,
     about_window_enabled: bool,
+    post_mortem_window_enabled: bool,
+    statistics_window_enabled: bool,
+    high_scores_window_enabled: bool,
+    config_problems_window_enabled: bool,
+    config_window_enabled: bool,
+    
+    config_problems: Vec<ConfigProblem>,
+    config_path: Option<PathBuf>,
+    user_config_text: String,
+    config_editor_text: String,
     
     selection_color: Color32,
     center_color: Color32,
     neighbor_color: Color32,
     highlight_colors: [Color32; 8],
+    wrong_flag_color: Color32,
+    missed_mine_color: Color32,
     
     shortcuts: Shortcuts,
+    capturing_shortcut: Option<usize>, // action waiting for a key in the Controls window
+    shortcut_conflict: Option<String>,
+    
+    board_cache: Option<(BoardCacheKey, Rect, Vec<Shape>)>, // the area covered is in logical points
+    board_rect: Rect, // screen area of the board in the last frame
+    show_minimap: bool,
+    minimap_docked: bool, // in the bottom left corner, otherwise it can be moved around
+    minimap: Option<(BoardCacheKey, TextureHandle)>,
 }
 
 impl MinesweeperViewController {
-    fn new(config_text: String) -> Self {
+    fn new(user_config: UserConfig, storage: Option<&dyn eframe::Storage>) -> Self {
         // Sanity check
         //println!("{}", std::mem::size_of::<CellState>());
 
@@ -183,18 +405,38 @@ impl MinesweeperViewController {
             
             next_selected_preset: None,
             presets: vec![],
+            default_preset: None,
+            new_preset_name: "".into(),
             
             game: None,
             start_time: None,
             end_time: None,
+            post_mortem: None,
+            left_clicks: 0,
+            right_clicks: 0,
+            chord_clicks: 0,
+            assists_used: false,
+            replay: vec![],
+            replay_playback: None,
+            
+            statistics: storage.and_then(|s| eframe::get_value(s, Statistics::STORAGE_KEY))
+                               .unwrap_or_default(),
+            statistics_dirty: false,
+            preferences_dirty: false,
+            presets_dirty: false,
             
             cursor_mode: CursorMode::ProbeAndMark,
             selected_highlighters: 1,
+            highlight_operands: [1, 2, 3],
             
             view_origin: Pos2::new(0.0, 20.0),
             zoom_factor: 1.0,
             cell_edge: 30.0,
             tile_spacings: [0.0, 0.0, 10.0, 10.0, 20.0, 20.0],
+            layout: BoardLayout::default(),
+            auto_layout: false,
+            slice: Slice::default(),
+            slice_view: false,
             
             show_timer_miliseconds: false,
             show_delta: true,
@@ -202,149 +444,340 @@ impl MinesweeperViewController {
             unlimited_zoom: false,
             probe_marked: false,
             neighbor_coords: None,
+            go_to_text: String::new(),
+            go_to_error: None,
+            find_status: None,
+            cursor_coords: None,
+            touch_seen: false,
+            touch_press_handled: false,
+            flag_mode: false,
             
             new_game_window_enabled: false,
+            presets_window_enabled: false,
+            layout_window_enabled: false,
+            go_to_window_enabled: false,
             rules_window_enabled: false,
             controls_window_enabled: false,
             about_window_enabled: false,
+            post_mortem_window_enabled: false,
+            statistics_window_enabled: false,
+            high_scores_window_enabled: false,
+            config_problems_window_enabled: false,
+            config_window_enabled: false,
+            
+            config_problems: vec![],
+            config_path: None,
+            user_config_text: "".into(),
+            config_editor_text: "".into(),
             
             selection_color: Color32::RED,
             center_color: Color32::LIGHT_RED,
             neighbor_color: Color32::LIGHT_BLUE,
             highlight_colors: [Color32::YELLOW, Color32::BROWN, Color32::LIGHT_GREEN, Color32::WHITE,
                                Color32::KHAKI, Color32::DARK_BLUE, Color32::DARK_GREEN, Color32::GOLD],
+            wrong_flag_color: Color32::from_rgb(255, 140, 0),
+            missed_mine_color: Color32::from_rgb(160, 32, 240),
+            
+            shortcuts: Shortcuts::default(),
+            capturing_shortcut: None,
+            shortcut_conflict: None,
             
-            shortcuts: Shortcuts::new(),
+            board_cache: None,
+            board_rect: Rect::ZERO,
+            show_minimap: false,
+            minimap_docked: true,
+            minimap: None,
         };
 
-        let config_table: Table = config_text.parse::<Table>().expect("Invalid configuration file");
-        // Load in presets
-        if let Some(val) = config_table.get("preset"){
-            for e in val.as_array().unwrap() {
-                let mut igs: InitialGameSettings = Default::default();
-                if let Some(n) = e.get("name") {
-                    igs.name = n.as_str().unwrap().into();
-                }
-                if let Some(size_value) = e.get("size") {
-                    if let Some(a) = size_value.as_array() {
-                        if a.len() != DIMENSIONS_COUNT {
-                            println!("Warning: `size` should be array of {} elements, {} found", DIMENSIONS_COUNT, a.len());
-                        }
-                        for ii in 0..min(a.len(), DIMENSIONS_COUNT) {
-                            if let Some(i) = a[ii].as_integer().map(|e| e.clamp(1, 100) as usize) {
-                                igs.size[ii] = i;
-                            } else {
-                                println!("Warning: value at index {} of `size` is invalid", ii);
-                            }
-                        }
-                    } else {
-                        println!("Warning: value of `size` is invalid");
-                    }
-                }
-                if let Some(wrap_value) = e.get("wrap") {
-                    if let Some(a) = wrap_value.as_array() {
-                        if a.len() != DIMENSIONS_COUNT {
-                            println!("Warning: `wrap` should be array of {} elements, {} found", DIMENSIONS_COUNT, a.len());
-                        }
-                        for ii in 0..min(a.len(), DIMENSIONS_COUNT) {
-                            if let Some(b) = a[ii].as_bool() {
-                                igs.wrap[ii] = b;
-                            } else {
-                                println!("Warning: value at index {} of `wrap` is invalid", ii);
-                            }
-                        }
-                    } else {
-                        println!("Warning: value of `wrap` is invalid");
-                    }
-                }
-                if let Some(mines_value) = e.get("mines") {
-                    if let Some(i) = mines_value.as_integer()
-                                        .map(|e| (e as u32)
-                                                .clamp(1, (igs.size.iter().fold(1, |p, v| p*v) - 1) as u32)) {
-                        igs.mines = i;
-                    } else {
-                        println!("Warning: value of `mines` is invalid");
-                    }
+        // Invalid values are left at defaults, problems are shown in a window
+        let (config, problems) = Config::layered(&user_config.text);
+        ret.config_problems = user_config.problem.into_iter().chain(problems).collect();
+        ret.config_path = user_config.path;
+        ret.user_config_text = user_config.text;
+        ret.apply_config(config);
+        ret.config_problems_window_enabled = !ret.config_problems.is_empty();
+        
+        ret
+    }
+
+    fn apply_config(&mut self, config: Config) {
+        self.presets = config.presets;
+        self.default_preset = config.default_preset;
+        self.next_selected_preset = None;
+        if let Some(i) = config.default_preset {
+            // Settings of a game in progress stay as they are
+            if self.game.is_none() {
+                self.current_initial_settings = self.presets[i].clone();
+            }
+            self.next_initial_settings = self.presets[i].clone();
+            self.next_selected_preset = Some(i as u32);
+        }
+        self.highlight_colors = config.highlight_colors.map(|[r, g, b, a]| Color32::from_rgba_unmultiplied(r, g, b, a));
+        self.show_timer_miliseconds = config.show_timer_miliseconds;
+        self.show_delta = config.show_delta;
+        self.show_neighbors = config.show_neighbors;
+        self.unlimited_zoom = config.unlimited_zoom;
+        self.probe_marked = config.probe_marked;
+        self.tile_spacings = config.tile_spacings;
+        self.layout = config.layout;
+        self.auto_layout = config.auto_layout;
+        self.show_minimap = config.show_minimap;
+        let (shortcuts, problems) = Shortcuts::from_config(&config.shortcuts);
+        self.shortcuts = shortcuts;
+        self.config_problems.extend(problems);
+    }
+
+    fn preferences(&self) -> Preferences {
+        Preferences {
+            show_timer_miliseconds: self.show_timer_miliseconds,
+            show_delta: self.show_delta,
+            show_neighbors: self.show_neighbors,
+            unlimited_zoom: self.unlimited_zoom,
+            probe_marked: self.probe_marked,
+            highlight_colors: self.highlight_colors.map(color_to_hex),
+            layout: self.layout.clone(),
+            auto_layout: self.auto_layout,
+            show_minimap: self.show_minimap,
+        }
+    }
+
+    fn save_preferences(&mut self, frame: &mut eframe::Frame) {
+        self.update_user_config(frame, "settings", |s, text| config::write_preferences(text, &s.preferences()));
+    }
+
+    // The whole preset list is written
+    fn save_presets(&mut self, frame: &mut eframe::Frame) {
+        self.update_user_config(frame, "presets",
+                                |s, text| config::write_presets(text, &s.presets, s.default_preset));
+    }
+
+    fn save_shortcuts(&mut self, frame: &mut eframe::Frame) {
+        self.update_user_config(frame, "shortcuts", |s, text| {
+            config::write_shortcuts(text, &s.shortcuts.bindings.map(|b| from_keyboard_shortcut(&b)))
+        });
+    }
+
+    // Rewrite part of the user's configuration, keeping the rest of it as it is
+    fn update_user_config(&mut self, frame: &mut eframe::Frame, what: &str,
+                          write: impl FnOnce(&Self, &str) -> Result<String, toml_edit::TomlError>) {
+        // The file might have been edited since it was loaded
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(text) = self.config_path.as_ref().and_then(|p| fs::read_to_string(p).ok()) {
+            self.user_config_text = text;
+        }
+        match write(self, &self.user_config_text) {
+            Ok(new_text) if new_text == self.user_config_text => {},
+            Ok(new_text) => self.store_user_config(frame, new_text),
+            Err(e) => self.report_config_problem(
+                format!("unable to save {}, the configuration is not valid TOML: {}", what, e.message().trim_end())),
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn store_user_config(&mut self, _frame: &mut eframe::Frame, text: String) {
+        if let Some(path) = &self.config_path {
+            let written = path.parent().map_or(Ok(()), fs::create_dir_all).and_then(|_| fs::write(path, &text));
+            if let Err(e) = written {
+                self.report_config_problem(format!("unable to save settings: {}", e));
+            }
+        }
+        self.user_config_text = text;
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn store_user_config(&mut self, frame: &mut eframe::Frame, text: String) {
+        if let Some(storage) = frame.storage_mut() {
+            storage.set_string(CONFIG_STORAGE_KEY, text.clone());
+            storage.flush();
+        }
+        self.user_config_text = text;
+    }
+
+    fn report_config_problem(&mut self, message: String) {
+        self.config_problems.push(ConfigProblem { severity: Severity::Error, line: None, key: "".into(), message });
+        self.config_problems_window_enabled = true;
+    }
+
+    fn reset(&mut self) {
+        self.game = None;
+        self.cursor_coords = None;
+        self.find_status = None;
+        self.replay_playback = None;
+        self.post_mortem = None;
+        self.post_mortem_window_enabled = false;
+        self.cursor_mode = CursorMode::ProbeAndMark;
+    }
+    
+    // Called once the game reaches Victory or Loss, `before` is the board before a losing action
+    fn end_game(&mut self, before: Option<&GameBoard>) {
+        self.end_time = Some(SystemTime::now());
+        if let Some(game) = &self.game {
+            self.post_mortem = before.and_then(|before| LossAnalysis::new(before, game));
+            self.post_mortem_window_enabled = self.post_mortem.is_some();
+            
+            if let (Some(start_time), Some(end_time)) = (self.start_time, self.end_time) {
+                let duration = end_time.duration_since(start_time).unwrap_or_default();
+                let key = self.statistics_key();
+                self.statistics.record(&key, game.state() == GameState::Victory, duration, game.bbbv());
+                if game.state() == GameState::Victory {
+                    self.statistics.record_high_score(&key, HighScore {
+                        date: SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default().as_secs(),
+                        time_ms: duration.as_millis() as u64,
+                        size: game.size(),
+                        wrap: game.wrap(),
+                        mines: game.mines_present(),
+                        seed: game.seed(),
+                        bbbv: game.bbbv(),
+                        assists: self.assists_used,
+                        replay: std::mem::take(&mut self.replay),
+                    });
                 }
-                if let Some(seed_value) = e.get("seed") {
-                    if let Some(s) = seed_value.as_str() {
-                        igs.seed = Some(s.into());
-                    } else {
-                        println!("Warning: value of `seed` is invalid");
-                    }
+                self.statistics_dirty = true;
+            }
+        }
+    }
+    
+    fn elapsed_ms(&self) -> u64 {
+        self.start_time.map_or(0, |t| SystemTime::now().duration_since(t).unwrap_or_default().as_millis() as u64)
+    }
+    
+    // Primary action of the Probe/Mark tool: starts the game or probes a cell
+    fn probe_action(&mut self, coords: [usize; DIMENSIONS_COUNT]) {
+        let time_ms = self.elapsed_ms();
+        self.find_status = None; // the board changes, there may be something to find again
+        if let Some(game) = &mut self.game {
+            if game.state() != GameState::Victory && game.state() != GameState::Loss {
+                // Only a losing probe needs the board as it was before it, for the post-mortem
+                let before = game.probe_explodes(coords, self.probe_marked).then(|| game.clone());
+                self.left_clicks += 1;
+                self.replay.push(ReplayAction {
+                    time_ms,
+                    kind: if self.probe_marked {ReplayActionKind::ProbeMarked} else {ReplayActionKind::Probe},
+                    coords
+                });
+                match game.probe_at(coords, self.probe_marked) {
+                    GameState::Victory | GameState::Loss => {
+                        self.end_game(before.as_ref());
+                    },
+                    GameState::Running => {}
                 }
-                ret.presets.push(igs);
             }
+        } else {
+            self.start(coords);
         }
-        
-        // Load in other settings
-        if let Some(val) = config_table.get("default_preset") {
-            if let Some(i) = val.as_integer().map(|e| e as usize) {
-                if i < ret.presets.len() {
-                    ret.current_initial_settings = ret.presets[i].clone();
-                    ret.next_initial_settings = ret.presets[i].clone();
-                    ret.next_selected_preset = Some(i as u32);
-                } else {
-                    println!("Warning: value of `default_preset` is outside presets range");
+    }
+    
+    // Probes all unmarked neighbors of a discovered number whose mines are all marked
+    fn chord_action(&mut self, coords: [usize; DIMENSIONS_COUNT]) {
+        let time_ms = self.elapsed_ms();
+        self.find_status = None;
+        if let Some(game) = &mut self.game {
+            if game.state() == GameState::Running && matches!(game.cell_at(coords), CellState::DiscoveredEmpty(..)) {
+                let before = game.chord_explodes(coords).then(|| game.clone());
+                self.chord_clicks += 1;
+                self.replay.push(ReplayAction { time_ms, kind: ReplayActionKind::Chord, coords });
+                match game.chord_at(coords) {
+                    GameState::Victory | GameState::Loss => {
+                        self.end_game(before.as_ref());
+                    },
+                    GameState::Running => {}
                 }
-            } else {
-                println!("Warning: value of `default_preset` is invalid");
             }
         }
-        
-        if let Some(val) = config_table.get("highlight_colors") {
-            let a = val.as_array().unwrap();
-            for ii in 0..8 {
-                ret.highlight_colors[ii] = Color32::from_hex(a[ii].as_str().unwrap()).unwrap();
+    }
+    
+    // Secondary action of the Probe/Mark tool: marks or unmarks a cell
+    fn mark_action(&mut self, coords: [usize; DIMENSIONS_COUNT]) {
+        let time_ms = self.elapsed_ms();
+        self.find_status = None;
+        if let Some(game) = &mut self.game {
+            if game.state() != GameState::Victory && game.state() != GameState::Loss {
+                self.right_clicks += 1;
+                self.replay.push(ReplayAction { time_ms, kind: ReplayActionKind::Mark, coords });
+                game.mark_at(coords);
             }
+        }
+    }
+    
+    // Re-create board of given winning run, if `watch` is set its replay is played back
+    fn open_high_score(&mut self, name: &str, high_score: &HighScore, watch: bool) {
+        let settings = InitialGameSettings {
+            name: name.into(),
+            size: high_score.size,
+            wrap: high_score.wrap,
+            mines: high_score.mines,
+            seed: Some(format!("{:016x}", high_score.seed)),
         };
+        self.current_initial_settings = settings.clone();
+        self.next_initial_settings = settings;
+        self.next_selected_preset = None;
+        self.reset();
         
-        if let Some(val) = config_table.get("show_timer_miliseconds") {
-            ret.show_timer_miliseconds = val.as_bool().unwrap();
-        }
-        if let Some(val) = config_table.get("show_delta") {
-            ret.show_delta = val.as_bool().unwrap();
-        }
-        if let Some(val) = config_table.get("show_neighbors") {
-            ret.show_neighbors = val.as_bool().unwrap();
-        }
-        if let Some(val) = config_table.get("unlimited_zoom") {
-            ret.unlimited_zoom = val.as_bool().unwrap();
-        }
-        if let Some(val) = config_table.get("probe_marked") {
-            ret.probe_marked = val.as_bool().unwrap();
+        if watch && !high_score.replay.is_empty() {
+            self.game = Some(GameBoard::new(high_score.size, high_score.wrap, high_score.mines,
+                                            None, Some(high_score.seed)));
+            self.start_time = Some(SystemTime::now());
+            self.end_time = None;
+            self.replay_playback = Some(ReplayPlayback {
+                actions: high_score.replay.clone(),
+                next: 0,
+            });
         }
-        
-        if let Some(val) = config_table.get("tile_spacings") {
-            let a = val.as_array().unwrap();
-            for ii in 0..4 {
-                let valf = a[ii].as_float().unwrap() as f32;
-                if valf >= 0.0 {
-                    ret.tile_spacings[ii] = valf;
+    }
+    
+    // Apply all replay actions that are due, finishes the playback after the last one
+    fn advance_replay(&mut self) {
+        let elapsed = self.elapsed_ms();
+        if let (Some(playback), Some(game)) = (&mut self.replay_playback, &mut self.game) {
+            while let Some(action) = playback.actions.get(playback.next) {
+                if action.time_ms > elapsed {
+                    break;
+                }
+                match action.kind {
+                    ReplayActionKind::Probe => {game.probe_at(action.coords, false);},
+                    ReplayActionKind::ProbeMarked => {game.probe_at(action.coords, true);},
+                    ReplayActionKind::Mark => game.mark_at(action.coords),
+                    ReplayActionKind::Chord => {game.chord_at(action.coords);},
                 }
+                playback.next += 1;
+            }
+            if playback.next >= playback.actions.len() {
+                self.end_time = Some(SystemTime::now());
+                self.replay_playback = None;
             }
         }
-        
-        ret
     }
-
-    fn reset(&mut self) {
-        self.game = None;
-        self.cursor_mode = CursorMode::ProbeAndMark;
+    
+    // Games are grouped by the preset they were started from, custom games by their settings
+    fn statistics_key(&self) -> String {
+        let settings = &self.current_initial_settings;
+        if let Some(preset) = self.presets.iter().find(|p| p.size == settings.size && p.wrap == settings.wrap
+                                                            && p.mines == settings.mines) {
+            preset.name.clone()
+        } else {
+            format!("Custom: {}, {} mines", settings.size_description(), settings.mines)
+        }
     }
 
     fn start(&mut self, initial: [usize; DIMENSIONS_COUNT]) {
         self.start_time = Some(SystemTime::now());
         self.end_time = None;
+        self.left_clicks = 1;
+        self.right_clicks = 0;
+        self.chord_clicks = 0;
+        self.assists_used = false;
+        self.replay = vec![ReplayAction { time_ms: 0, kind: ReplayActionKind::ProbeMarked, coords: initial }];
         if let Some(seed) = &self.current_initial_settings.seed {
             self.game = Some(GameBoard::new(self.current_initial_settings.size,
                                             self.current_initial_settings.wrap,
                                             self.current_initial_settings.mines,
                                             None,
                                             u64::from_str_radix(&seed, 16).ok()));
+            let game = self.game.as_ref().unwrap();
+            let before = game.probe_explodes(initial, true).then(|| game.clone());
             match self.game.as_mut().unwrap().probe_at(initial, true) {
                 GameState::Victory | GameState::Loss => {
-                    self.end_time = Some(SystemTime::now());
+                    self.end_game(before.as_ref());
                 },
                 GameState::Running => {}
             }
@@ -357,86 +790,416 @@ impl MinesweeperViewController {
         }
     }
 
+    // Placement of the current board's cells (or of the shown slices), in logical points
+    fn geometry(&self) -> BoardGeometry {
+        let InitialGameSettings { size, wrap, .. } = self.current_initial_settings;
+        if self.slice_view {
+            BoardGeometry::slice(&self.slice, size, wrap, self.tile_spacings, self.cell_edge)
+        } else {
+            BoardGeometry::new(&self.layout, size, self.tile_spacings, self.cell_edge)
+        }
+    }
+    
     // Translate and Scale from screen coordinates to cell coordinates
-    // Uses modular cutoff to decide in constant time whether mouse is over any cell:
-    //
-    //   If current position modulo (x*cell+(x-1)*space_1+space_2)
-    //     is larger than (x*cell+(x-1)*space_1), it must be outside a cell, otherwise repeat:
-    //   |      |         |      |         |      |         |      |         |
-    //   | cell | space_1 | cell | space_1 | cell | space_1 | cell | space_2 |
-    //   |      |         |      |         |      |         |      |         |
     fn get_coords(&self, pos: Pos2) -> Option<[usize; DIMENSIONS_COUNT]> {
-        if pos.x < self.view_origin.x || pos.y < self.view_origin.y {
-            return None;
+        let point = (pos - self.view_origin) / self.zoom_factor;
+        self.geometry().cell_at([point.x, point.y])
+    }
+    
+    // Scale and Translate from logical points to screen coordinates
+    fn sc_tr(&self, xx: f32, yy: f32) -> Pos2 {
+        Pos2::new(xx * self.zoom_factor, yy * self.zoom_factor) + self.view_origin.to_vec2()
+    }
+    
+    // Whole board at `scale` pixels per logical point, a pixel per cell at least. Flags, highlights and
+    // revealed cells have their own colours, the gaps between cells are left transparent.
+    fn minimap_image(&self, geometry: &BoardGeometry, scale: f32) -> ColorImage {
+        let [width, height] = geometry.extent().map(|e| ((e * scale).ceil() as usize).max(1));
+        let mut image = ColorImage::new([width, height], Color32::TRANSPARENT);
+        for (coords, [ulc_x, ulc_y], faded) in geometry.cells() {
+            let color = match self.game.as_ref().map(|g| g.cell_at(coords)) {
+                Some(CellState::ExplodedMine(_)) => Color32::BLACK,
+                Some(CellState::MarkedMine(_) | CellState::MarkedEmpty(..)) => Color32::RED,
+                Some(state) if state.highlight_groups() > 0
+                    => self.highlight_colors[state.highlight_groups().trailing_zeros() as usize],
+                Some(CellState::DiscoveredEmpty(..)) => Color32::LIGHT_GRAY,
+                _ => Color32::GRAY,
+            };
+            let color = if faded {color.gamma_multiply(PREVIEW_OPACITY)} else {color};
+            let span = |start: f32, size: usize| {
+                let first = ((start * scale) as usize).min(size - 1);
+                first..(((start + self.cell_edge) * scale).ceil() as usize).clamp(first + 1, size)
+            };
+            for yy in span(ulc_y, height) {
+                for xx in span(ulc_x, width) {
+                    image.pixels[yy * width + xx] = color;
+                }
+            }
         }
+        image
+    }
+    
+    // Everything that only changes with `board_cache_key`, relative to the view origin
+    fn board_cache_key(&self, ctx: &egui::Context) -> BoardCacheKey {
+        BoardCacheKey {
+            revision: self.game.as_ref().map(GameBoard::revision),
+            zoom_factor: self.zoom_factor,
+            pixels_per_point: ctx.pixels_per_point(),
+            cell_edge: self.cell_edge,
+            tile_spacings: self.tile_spacings,
+            settings: (self.current_initial_settings.size, self.current_initial_settings.wrap),
+            layout: self.layout.clone(),
+            slice: Some(self.slice.clone()).filter(|_| self.slice_view),
+            show_delta: self.show_delta,
+            highlight_colors: self.highlight_colors,
+        }
+    }
+    
+    // Everything around the cells in `area` (in logical points) that isn't drawn anew each frame,
+    // relative to the view origin
+    fn board_shapes(&self, painter: &egui::Painter, geometry: &BoardGeometry, area: Rect) -> Vec<Shape> {
+        let at = |xx: f32, yy: f32| Pos2::new(xx * self.zoom_factor, yy * self.zoom_factor);
+        // Since drawing text is somewhat expensive, cells are only drawn when their text can be read
+        let mut shapes = if self.zoom_factor < SUMMARY_ZOOM {
+            self.block_summary_shapes(painter, geometry, area)
+        } else {
+            self.cell_shapes(painter, geometry, area)
+        };
         
-        let [c_xx, c_yy, c_zz, c_uu, c_vv, c_ww] = self.current_initial_settings.size;
-        let [sp_xx, sp_yy, sp_zz, sp_uu, sp_vv, sp_ww] = self.tile_spacings;
-        
-        // Sizes of blocks (cells + inner spacing)
-        let x_block_size = c_xx as f32 * self.cell_edge + (c_xx - 1) as f32 * sp_xx;
-        let y_block_size = c_yy as f32 * self.cell_edge + (c_yy - 1) as f32 * sp_yy;
-        let z_block_size = c_zz as f32 * x_block_size + (c_zz - 1) as f32 * sp_zz;
-        let u_block_size = c_uu as f32 * y_block_size + (c_uu - 1) as f32 * sp_uu;
-        // let v_block_size = c_vv as f32 * z_block_size + (c_vv - 1) as f32 * sp_vv;
-        // let w_block_size = c_ww as f32 * u_block_size + (c_ww - 1) as f32 * sp_ww;
+        // Slice labels
+        if self.slice_view {
+            for panel in geometry.panels() {
+                shapes.push(text_shape(painter, at(panel.origin[0], panel.origin[1] - self.cell_edge/2.0),
+                                       Align2::LEFT_CENTER, &panel.label, FontId::proportional(20.0 * self.zoom_factor),
+                                       if panel.faded {Color32::DARK_GRAY} else {Color32::BLACK}));
+            }
+        }
         
-        // Get logical points
-        let (dx, dy) = ((pos.x - self.view_origin.x) / self.zoom_factor,
-                        (pos.y - self.view_origin.y) / self.zoom_factor);
+        shapes
+    }
+    
+    // Innermost blocks overlapping `area` as single tiles: the revealed part of the block fills the tile
+    // from the bottom, the flag count is written over it and blocks with nothing left to probe get a green frame
+    fn block_summary_shapes(&self, painter: &egui::Painter, geometry: &BoardGeometry, area: Rect) -> Vec<Shape> {
+        let at = |xx: f32, yy: f32| Pos2::new(xx * self.zoom_factor, yy * self.zoom_factor);
+        let mut shapes = Vec::new();
         
-        // Modulo largest period
-        let (dx_m1, dy_m1) = (dx as f32 % (z_block_size + sp_vv),
-                              dy as f32 % (u_block_size + sp_ww));
-        if dx_m1 > z_block_size || dy_m1 > u_block_size {
-            return None;
-        }
-        // Modulo middle period
-        let (dx_m2, dy_m2) = (dx_m1 % (x_block_size + sp_zz),
-                              dy_m1 % (y_block_size + sp_uu));
-        if dx_m2 > x_block_size || dy_m2 > y_block_size {
-            return None;
+        let size = self.current_initial_settings.size;
+        let [width, height] = geometry.block_extent();
+        let [across, down] = geometry.block_axes();
+        let inner = |axis: Option<usize>| 0..axis.map_or(1, |a| size[a]);
+        let count = inner(across).len() * inner(down).len();
+        for (block, [ulc_x, ulc_y], faded) in geometry.blocks_in(area.min.into(), area.max.into()) {
+            let (mut revealed, mut flags, mut exploded) = (0, 0, false);
+            if let Some(game) = &self.game {
+                for (ii, jj) in inner(across).cartesian_product(inner(down)) {
+                    let mut coords = block;
+                    if let Some(a) = across {coords[a] = ii;}
+                    if let Some(a) = down {coords[a] = jj;}
+                    match game.cell_at(coords) {
+                        CellState::DiscoveredEmpty(..) => revealed += 1,
+                        CellState::MarkedMine(_) | CellState::MarkedEmpty(..) => flags += 1,
+                        CellState::ExplodedMine(_) => exploded = true,
+                        CellState::UndiscoveredMine(_) | CellState::UndiscoveredEmpty(..) => {},
+                    }
+                }
+            }
+            let solved = revealed + flags == count
+                         || self.game.as_ref().is_some_and(|g| g.state() == GameState::Victory);
+            
+            let fade = |color: Color32| if faded {color.gamma_multiply(PREVIEW_OPACITY)} else {color};
+            let rect = Rect::from_min_max(at(ulc_x, ulc_y), at(ulc_x + width, ulc_y + height));
+            shapes.push(Shape::rect_filled(rect, Rounding::ZERO, fade(if exploded {Color32::RED} else {Color32::GRAY})));
+            let revealed_height = rect.height() * revealed as f32 / count as f32;
+            shapes.push(Shape::rect_filled(Rect::from_min_max(Pos2::new(rect.min.x, rect.max.y - revealed_height), rect.max),
+                                           Rounding::ZERO, fade(Color32::LIGHT_GRAY)));
+            shapes.push(Shape::rect_stroke(rect, Rounding::ZERO, if solved {
+                Stroke::new(4.0 * self.zoom_factor, fade(Color32::GREEN))
+            } else {
+                Stroke::new(2.0 * self.zoom_factor, fade(Color32::BLACK))
+            }));
+            
+            let font_size = rect.width().min(rect.height()) / 3.0;
+            if flags > 0 && font_size >= 10.0 {
+                shapes.push(text_shape(painter, rect.center(), Align2::CENTER_CENTER, format!("🚩{}", flags),
+                                       FontId::proportional(font_size), fade(Color32::BLACK)));
+            }
         }
-        // Modulo smallest period
-        let (dx_m3, dy_m3) = (dx_m2 % (self.cell_edge + sp_xx),
-                              dy_m2 % (self.cell_edge + sp_yy));
-        if dx_m3 > self.cell_edge || dy_m3 > self.cell_edge {
-            return None;
+        shapes
+    }
+    
+    // Cell contents, lines and highlights of the cells overlapping `area`
+    fn cell_shapes(&self, painter: &egui::Painter, geometry: &BoardGeometry, area: Rect) -> Vec<Shape> {
+        let at = |xx: f32, yy: f32| Pos2::new(xx * self.zoom_factor, yy * self.zoom_factor);
+        let mut shapes = Vec::new();
+        
+        let basic_stroke = Stroke::new(2.0 * self.zoom_factor, egui::Color32::BLACK);
+        let harder_stroke = Stroke::new(4.0 * self.zoom_factor, egui::Color32::BLACK);
+        let highlight_strokes = self.highlight_colors.map(|x| Stroke::new(2.0 * self.zoom_factor, x));
+        let preview_stroke = |stroke: Stroke| Stroke::new(stroke.width, stroke.color.gamma_multiply(PREVIEW_OPACITY));
+        
+        // Cell contents
+        let background_color = Color32::GRAY;
+        if let Some(game) = &self.game {
+            for (coords, [ulc_x, ulc_y], faded) in geometry.cells_in(area.min.into(), area.max.into()) {
+                let (symbol, color) = match game.cell_at(coords) {
+                    CellState::UndiscoveredMine(_)
+                        => if game.state() == GameState::Victory {
+                                ("💣".into(), Color32::GREEN)
+                           } else if game.state() == GameState::Loss {
+                                ("💣".into(), Color32::RED)
+                           } else {
+                                ("".into(), Color32::GRAY)
+                           },
+                    CellState::MarkedMine(_)
+                        => if game.state() == GameState::Victory || game.state() == GameState::Loss {
+                                ("🚩".into(), Color32::GREEN)
+                           } else {
+                                ("🚩".into(), Color32::GRAY)
+                           },
+                    CellState::ExplodedMine(_) => ("💥".into(), Color32::RED),
+                    CellState::UndiscoveredEmpty(..) => ("".into(), Color32::GRAY),
+                    CellState::MarkedEmpty(..)
+                        => if game.state() == GameState::Victory || game.state() == GameState::Loss {
+                                ("🚩".into(), Color32::RED)
+                           } else {
+                                ("🚩".into(), Color32::GRAY)
+                           },
+                    CellState::DiscoveredEmpty(mc, delta, _)
+                        => (if mc == 0 && delta == 0 {"".into()}
+                            else {format!("{}", if self.show_delta {delta} else {mc as i32})},
+                            Color32::LIGHT_GRAY),
+                };
+                
+                let fade = |color: Color32| if faded {color.gamma_multiply(PREVIEW_OPACITY)} else {color};
+                
+                // Only paint squares with different color than the current background
+                if color != background_color {
+                    shapes.push(
+                        Shape::rect_filled(
+                            Rect::from_min_max(at(ulc_x, ulc_y), at(ulc_x + self.cell_edge, ulc_y + self.cell_edge)),
+                            Rounding::ZERO, fade(color)
+                        )
+                    );
+                }
+                
+                if symbol != "" {
+                    shapes.push(text_shape(painter, at(ulc_x + self.cell_edge/2.0, ulc_y + self.cell_edge/2.0),
+                                           Align2::CENTER_CENTER, symbol, FontId::proportional(25.0 * self.zoom_factor),
+                                           fade(Color32::BLACK)));
+                }
+            }
         }
         
-        // Is cell-like, get coords
-        let (xx, yy) = ((dx_m2 / (self.cell_edge + sp_xx)) as usize,
-                        (dy_m2 / (self.cell_edge + sp_yy)) as usize);
-        let (zz, uu) = ((dx_m1 / (x_block_size + sp_zz)) as usize,
-                        (dy_m1 / (y_block_size + sp_uu)) as usize);
-        let (vv, ww) = ((dx / (z_block_size + sp_vv)) as usize,
-                        (dy / (u_block_size + sp_ww)) as usize);
+        // Lines, sides on the edge of the innermost blocks are thicker
+        for (coords, [ulc_x, ulc_y], faded) in geometry.cells_in(area.min.into(), area.max.into()) {
+            let (basic_stroke, harder_stroke) = if faded {
+                (preview_stroke(basic_stroke), preview_stroke(harder_stroke))
+            } else {
+                (basic_stroke, harder_stroke)
+            };
+            for end in [false, true] {
+                let side = if end {self.cell_edge} else {0.0};
+                shapes.push(
+                    Shape::line_segment(
+                        [at(ulc_x + side, ulc_y), at(ulc_x + side, ulc_y + self.cell_edge)],
+                        if geometry.is_block_edge(coords, 0, end) {harder_stroke} else {basic_stroke}));
+                shapes.push(
+                    Shape::line_segment(
+                        [at(ulc_x, ulc_y + side), at(ulc_x + self.cell_edge, ulc_y + side)],
+                        if geometry.is_block_edge(coords, 1, end) {harder_stroke} else {basic_stroke}));
+            }
+        }
         
-        // Check if actual cell within grid bounds
-        if xx >= c_xx || yy >= c_yy || zz >= c_zz || uu >= c_uu || vv >= c_vv || ww >= c_ww {
-            return None;
+        // Highlights
+        const HIGHLIGHT_SPACING: f32 = 2.5;
+        for (coords, [ulc_x, ulc_y], faded) in geometry.cells_in(area.min.into(), area.max.into()) {
+            if let Some(game) = &self.game {
+                match game.cell_at(coords) {
+                    CellState::UndiscoveredMine(g) | CellState::MarkedMine(g)
+                    | CellState::ExplodedMine(g) | CellState::UndiscoveredEmpty(.., g)
+                    | CellState::MarkedEmpty(.., g) | CellState::DiscoveredEmpty(.., g)
+                        => {
+                        let mut next_start_group = 0;
+                        if g > 0 { for current_side in 0..8 {
+                            for highlight_group in (next_start_group..8).chain(0..next_start_group) {
+                                if (g & (1 << highlight_group)) > 0 {
+                                    let (mut p1x, mut p1y) = (ulc_x, ulc_y);
+                                    let (mut p2x, mut p2y) = (ulc_x, ulc_y);
+                                    match current_side {
+                                        0 | 6 | 7 => {p1x += HIGHLIGHT_SPACING;},
+                                        1 | 5 => {p1x += self.cell_edge/2.0;},
+                                        2 | 3 | 4 => {p1x += self.cell_edge-HIGHLIGHT_SPACING;},
+                                        _ => {}
+                                    };
+                                    match current_side {
+                                        0 | 1 | 2 => {p1y += HIGHLIGHT_SPACING;},
+                                        3 | 7 => {p1y += self.cell_edge/2.0;},
+                                        4 | 5 | 6 => {p1y += self.cell_edge-HIGHLIGHT_SPACING;},
+                                        _ => {}
+                                    };
+                                    match current_side {
+                                        0 | 4 => {p2x += self.cell_edge/2.0;},
+                                        1 | 2 | 3 => {p2x += self.cell_edge-HIGHLIGHT_SPACING;},
+                                        5 | 6 | 7 => {p2x += HIGHLIGHT_SPACING;},
+                                        _ => {}
+                                    };
+                                    match current_side {
+                                        0 | 1 | 7 => {p2y += HIGHLIGHT_SPACING;},
+                                        2 | 6 => {p2y += self.cell_edge/2.0;},
+                                        3 | 4 | 5 => {p2y += self.cell_edge-HIGHLIGHT_SPACING;},
+                                        _ => {}
+                                    };
+                                    
+                                    shapes.push(
+                                        Shape::line_segment([at(p1x, p1y),
+                                                             at(p2x, p2y)],
+                                                            if faded {preview_stroke(highlight_strokes[highlight_group])}
+                                                            else {highlight_strokes[highlight_group]}));
+                                    next_start_group = (highlight_group + 1) % 8;
+                                    break;
+                                }
+                            }
+                        }}
+                    }
+                };
+            }
         }
         
-        return Some([xx, yy, zz, uu, vv, ww]);
+        shapes
     }
     
-    // Scale and Translate from logical points to screen coordinates
-    fn sc_tr(&self, xx: f32, yy: f32) -> Pos2 {
-        Pos2::new(xx * self.zoom_factor, yy * self.zoom_factor) + self.view_origin.to_vec2()
+    // Screen rectangle occupied by given cell, the one in the current slice if there are more
+    fn cell_rect(&self, coords: [usize; DIMENSIONS_COUNT]) -> Option<Rect> {
+        self.cell_rects(&self.geometry(), coords).next()
+    }
+    // Every place given cell is drawn at, none when it's outside the shown slices
+    fn cell_rects<'a>(&'a self, geometry: &'a BoardGeometry, coords: [usize; DIMENSIONS_COUNT])
+            -> impl Iterator<Item = Rect> + 'a {
+        geometry.cell_origins(coords).map(|[ulc_x, ulc_y]| Rect::from_min_max(
+            self.sc_tr(ulc_x, ulc_y), self.sc_tr(ulc_x + self.cell_edge, ulc_y + self.cell_edge)))
+    }
+    
+    fn toggle_slice_view(&mut self, screen_size: Pos2) {
+        self.slice_view = !self.slice_view;
+        self.zoom_to_fit(screen_size);
     }
     
-    // Cumulative spacing in screen axis directions
-    fn cum_spc_x(&self, xx: usize, zz: usize, vv: usize) -> f32 {
-        let [c_xx, _, _, _, _, _] = self.current_initial_settings.size;
-        let [sp_xx, _, sp_zz, _, sp_vv, _] = self.tile_spacings;
-        return (xx+zz*(c_xx-1)+vv*(c_xx-1)) as f32*sp_xx + (zz+vv*(c_xx-1)) as f32*sp_zz + vv as f32*sp_vv;
+    // Layout chosen by the player, switches the auto layout off
+    fn set_layout(&mut self, layout: BoardLayout, screen_size: Pos2) {
+        self.layout = layout;
+        self.auto_layout = false;
+        self.preferences_dirty = true;
+        self.zoom_to_fit(screen_size);
     }
-    fn cum_spc_y(&self, yy: usize, uu: usize, ww: usize) -> f32 {
-        let [_, c_yy, _, _, _, _] = self.current_initial_settings.size;
-        let [_, sp_yy, _, sp_uu, _, sp_ww] = self.tile_spacings;
-        return (yy+uu*(c_yy-1)+ww*(c_yy-1)) as f32*sp_yy + (uu+ww*(c_yy-1)) as f32*sp_uu + ww as f32*sp_ww;
+    
+    // Cell under the pointer, unless a window or a menu is in the way
+    fn hovered_cell(&self, ctx: &egui::Context) -> Option<[usize; DIMENSIONS_COUNT]> {
+        let pos = ctx.pointer_hover_pos().filter(|&pos| self.board_rect.contains(pos))?;
+        if ctx.layer_id_at(pos).is_some_and(|layer| layer.order != egui::Order::Background) {
+            return None;
+        }
+        self.get_coords(pos)
     }
     
+    // Line about the hovered cell (or the one under the keyboard cursor) for the bottom panel: where it is,
+    // what it is and what's around it, also split by the axes along which the neighbors differ from it
+    fn inspector_text(&self, game: &GameBoard, coords: [usize; DIMENSIONS_COUNT]) -> String {
+        let over = game.state() != GameState::Running;
+        let state = match game.cell_at(coords) {
+            CellState::UndiscoveredMine(_) if over => "mine".to_string(),
+            CellState::UndiscoveredMine(_) | CellState::UndiscoveredEmpty(..) => "undiscovered".to_string(),
+            CellState::MarkedEmpty(..) if over => "wrongly flagged".to_string(),
+            CellState::MarkedMine(_) | CellState::MarkedEmpty(..) => "flagged".to_string(),
+            CellState::ExplodedMine(_) => "exploded".to_string(),
+            CellState::DiscoveredEmpty(mc, ..) => format!("revealed {}", mc),
+        };
+        let counts = |c: NeighborCounts| format!("{}/{}/{}", c.undiscovered, c.flagged, c.revealed);
+        let (total, by_axis) = game.neighbor_counts(coords);
+        let mut text = format!("{} {}  Neighbors (undiscovered/flagged/revealed): {}",
+                               format_coords(coords), state, counts(total));
+        // Same as the delta, mines around that aren't flagged yet
+        if let CellState::DiscoveredEmpty(mc, ..) = game.cell_at(coords) {
+            text += &format!(", {} mines left", mc as i64 - total.flagged as i64);
+        }
+        let axes = (0..DIMENSIONS_COUNT).filter(|&a| game.size()[a] > 1)
+                                        .map(|a| format!("{} {}", AXIS_NAMES[a], counts(by_axis[a]))).join(", ");
+        text + "  By axis: " + &axes
+    }
+    
+    // Keyboard cursor, if it's inside the current board
+    fn keyboard_cursor(&self) -> Option<[usize; DIMENSIONS_COUNT]> {
+        let size = self.current_initial_settings.size;
+        self.cursor_coords.filter(|c| (0..DIMENSIONS_COUNT).all(|i| c[i] < size[i]))
+    }
+
+    // Move the keyboard cursor by one cell along `axis`, wrapping around on wrapping dimensions.
+    // The first move only shows the cursor at `start`, or in the middle of the board.
+    fn move_keyboard_cursor(&mut self, axis: usize, forward: bool, start: Option<[usize; DIMENSIONS_COUNT]>) {
+        let InitialGameSettings { size, wrap, .. } = self.current_initial_settings;
+        let Some(mut coords) = self.keyboard_cursor() else {
+            self.cursor_coords = Some(start.unwrap_or(size.map(|s| s / 2)));
+            return;
+        };
+        let c = coords[axis];
+        coords[axis] = match (forward, wrap[axis]) {
+            (true, _) if c + 1 < size[axis] => c + 1,
+            (true, true) => 0,
+            (false, _) if c > 0 => c - 1,
+            (false, true) => size[axis] - 1,
+            _ => c,
+        };
+        self.cursor_coords = Some(coords);
+    }
+
+    // Pan the view so that given cell is inside `area` (with a cell of space around it)
+    fn scroll_to_cell(&mut self, coords: [usize; DIMENSIONS_COUNT], area: Rect) {
+        let Some(rect) = self.cell_rect(coords) else {
+            return;
+        };
+        let margin = self.cell_edge * self.zoom_factor;
+        if rect.min.x < area.min.x + margin {
+            self.view_origin.x += area.min.x + margin - rect.min.x;
+        } else if rect.max.x > area.max.x - margin {
+            self.view_origin.x -= rect.max.x - (area.max.x - margin);
+        }
+        if rect.min.y < area.min.y + margin {
+            self.view_origin.y += area.min.y + margin - rect.min.y;
+        } else if rect.max.y > area.max.y - margin {
+            self.view_origin.y -= rect.max.y - (area.max.y - margin);
+        }
+    }
+
+    // Put the keyboard cursor on given cell and center the view on it, in slice view on the slice it's in
+    fn go_to(&mut self, coords: [usize; DIMENSIONS_COUNT]) {
+        if self.slice_view {
+            self.slice.fixed = coords;
+        }
+        self.cursor_coords = Some(coords);
+        if let Some(rect) = self.cell_rect(coords) {
+            self.view_origin += self.board_rect.center() - rect.center();
+        }
+    }
+
+    // Go to the next cell matching the query after the keyboard cursor. Counts as an assist,
+    // since it points out where progress can be made.
+    fn find_next(&mut self, query: CellQuery) {
+        let Some(game) = &self.game else {
+            return;
+        };
+        match game.find_next(query, self.keyboard_cursor()) {
+            Some(coords) => {
+                self.assists_used |= game.state() == GameState::Running && self.replay_playback.is_none();
+                self.find_status = None;
+                self.go_to(coords);
+            },
+            None => self.find_status = Some(match query {
+                CellQuery::OpenNumber => "No numbers with mines left to flag",
+                CellQuery::SafeCell => "No cells next to solved numbers",
+            }),
+        }
+    }
+
     fn try_set_cursor(&mut self, mode: CursorMode) {
         match mode {
             CursorMode::ProbeAndMark => {
@@ -450,24 +1213,44 @@ impl MinesweeperViewController {
         }
     }
     
+    // Finger held in place on the board for `LONG_PRESS_SECONDS`, reported once per touch.
+    // Moving the finger or adding another one cancels it, the touch is then not a tap either.
+    fn long_press(&mut self, ctx: &egui::Context) -> bool {
+        let (pressed, touching, held_for, moved) = ctx.input(|i| (
+            i.pointer.any_pressed(),
+            i.any_touches() && i.pointer.primary_down(),
+            i.pointer.press_start_time().map_or(0.0, |t| i.time - t),
+            i.pointer.is_decidedly_dragging() || i.multi_touch().is_some(),
+        ));
+        if pressed {
+            self.touch_press_handled = false;
+        }
+        if !touching || self.touch_press_handled {
+            return false;
+        }
+        self.touch_seen = true;
+        if moved {
+            self.touch_press_handled = true;
+            false
+        } else if held_for >= LONG_PRESS_SECONDS {
+            self.touch_press_handled = true;
+            true
+        } else {
+            ctx.request_repaint_after(Duration::from_secs_f64(LONG_PRESS_SECONDS - held_for));
+            false
+        }
+    }
+
     fn reset_view(&mut self) {
         self.view_origin = Pos2::new(0.0, 20.0);
         self.zoom_factor = 1.0;
     }
     
     fn zoom_to_fit(&mut self, screen_size: Pos2) {
-        let [c_xx, c_yy, c_zz, c_uu, c_vv, c_ww] = self.current_initial_settings.size;
-        let [sp_xx, sp_yy, sp_zz, sp_uu, sp_vv, sp_ww] = self.tile_spacings;
-        
         // TODO: allow user to set the padding
         let (padding_x, padding_y) = (5.0, 5.0);
         
-        let x_block_size = c_xx as f32 * self.cell_edge + (c_xx - 1) as f32 * sp_xx;
-        let y_block_size = c_yy as f32 * self.cell_edge + (c_yy - 1) as f32 * sp_yy;
-        let z_block_size = c_zz as f32 * x_block_size + (c_zz - 1) as f32 * sp_zz;
-        let u_block_size = c_uu as f32 * y_block_size + (c_uu - 1) as f32 * sp_uu;
-        let v_block_size = c_vv as f32 * z_block_size + (c_vv - 1) as f32 * sp_vv;
-        let w_block_size = c_ww as f32 * u_block_size + (c_ww - 1) as f32 * sp_ww;
+        let [v_block_size, w_block_size] = self.geometry().extent();
         
         let x_factor = (screen_size.x - 2.0*padding_x) / v_block_size;
         let y_factor = (screen_size.y - 40.0 - 2.0*padding_y) / w_block_size;
@@ -487,11 +1270,43 @@ impl MinesweeperViewController {
 }
 
 impl eframe::App for MinesweeperViewController {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        if self.show_timer_miliseconds {
-            ctx.request_repaint();
-        } else {
-            ctx.request_repaint_after(Duration::new(0,500));
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, Statistics::STORAGE_KEY, &self.statistics);
+        self.statistics_dirty = false;
+    }
+    
+    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        // Write statistics right away, so finished games are not lost when the app is killed
+        if self.statistics_dirty {
+            if let Some(storage) = frame.storage_mut() {
+                self.save(storage);
+                storage.flush();
+            }
+        }
+        // Save changed preferences, but not while a colour is being dragged around
+        if self.preferences_dirty && !ctx.input(|i| i.pointer.any_down()) {
+            self.preferences_dirty = false;
+            self.save_preferences(frame);
+        }
+        // Renamed presets are saved once the name is no longer being typed
+        if self.presets_dirty && !ctx.wants_keyboard_input() {
+            self.presets_dirty = false;
+            self.save_presets(frame);
+        }
+        
+        // Input repaints on its own, otherwise only the timer and replays need new frames
+        if self.start_time.is_some() && self.end_time.is_none() {
+            if self.show_timer_miliseconds {
+                ctx.request_repaint();
+            } else {
+                ctx.request_repaint_after(Duration::from_millis(1000 - self.elapsed_ms() % 1000));
+            }
+        }
+        if self.replay_playback.is_some() {
+            self.advance_replay();
+            if let Some(action) = self.replay_playback.as_ref().and_then(|p| p.actions.get(p.next)) {
+                ctx.request_repaint_after(Duration::from_millis(action.time_ms.saturating_sub(self.elapsed_ms())));
+            }
         }
         
         let mut new_game_window_enabled = self.new_game_window_enabled;
@@ -557,29 +1372,318 @@ impl eframe::App for MinesweeperViewController {
                     }
                 }
                 
-                ui.with_layout(egui::Layout::left_to_right(egui::Align::TOP), |ui| {
-                    ui.label("Seed: ");
+                ui.with_layout(egui::Layout::left_to_right(egui::Align::TOP), |ui| {
+                    ui.label("Seed: ");
+                    
+                    if let Some(ref mut s) = self.next_initial_settings.seed {
+                        ui.add_enabled(true, egui::TextEdit::singleline(&mut *s));
+                    } else {
+                        ui.add_enabled(false, egui::TextEdit::singleline(&mut ""));
+                    };
+                });
+                
+                ui.with_layout(egui::Layout::left_to_right(egui::Align::TOP), |ui| {
+                    if ui.button("Reset").clicked() {
+                        self.next_initial_settings = self.current_initial_settings.clone();
+                    }
+                    if ui.button("Start").clicked() {
+                        self.current_initial_settings = self.next_initial_settings.clone();
+                        self.new_game_window_enabled = false;
+                        self.reset();
+                    }
+                });
+            });
+        }
+        self.new_game_window_enabled = self.new_game_window_enabled && new_game_window_enabled;
+        
+        let mut presets_window_enabled = self.presets_window_enabled;
+        if presets_window_enabled {
+            enum PresetAction { MoveUp(usize), MoveDown(usize), Duplicate(usize), Delete(usize) }
+            let mut action = None;
+            egui::Window::new("Presets")
+                .open(&mut presets_window_enabled).show(ctx, |ui| {
+                
+                ui.label(format!("Settings of the next game: {}, {} mines",
+                                 self.next_initial_settings.size_description(), self.next_initial_settings.mines));
+                ui.horizontal(|ui| {
+                    ui.add(egui::TextEdit::singleline(&mut self.new_preset_name).hint_text("Name"));
+                    let exists = self.presets.iter().any(|p| p.name == self.new_preset_name);
+                    let save_button = Button::new(if exists {"Replace preset"} else {"Save as preset"});
+                    if ui.add_enabled(!self.new_preset_name.trim().is_empty(), save_button).clicked() {
+                        let mut preset = self.next_initial_settings.clone();
+                        preset.name = self.new_preset_name.trim().into();
+                        preset.seed = preset.seed.filter(|s| u64::from_str_radix(s, 16).is_ok());
+                        match self.presets.iter().position(|p| p.name == preset.name) {
+                            Some(ii) => {
+                                self.presets[ii] = preset;
+                                self.next_selected_preset = Some(ii as u32);
+                            },
+                            None => {
+                                self.presets.push(preset);
+                                self.next_selected_preset = Some(self.presets.len() as u32 - 1);
+                            },
+                        }
+                        self.new_preset_name.clear();
+                        self.presets_dirty = true;
+                    }
+                });
+                ui.separator();
+                
+                egui::ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
+                    egui::Grid::new("presets_grid").striped(true).show(ui, |ui| {
+                        ui.label("Default");
+                        ui.label("Name");
+                        ui.label("Dimensions");
+                        ui.label("Mines");
+                        ui.end_row();
+                        
+                        let count = self.presets.len();
+                        for ii in 0..count {
+                            if ui.radio(self.default_preset == Some(ii), "").clicked() {
+                                self.default_preset = Some(ii);
+                                self.presets_dirty = true;
+                            }
+                            if ui.text_edit_singleline(&mut self.presets[ii].name).changed() {
+                                self.presets_dirty = true;
+                            }
+                            ui.label(self.presets[ii].size_description());
+                            ui.label(self.presets[ii].mines.to_string());
+                            if ui.add_enabled(ii > 0, Button::new("⏶")).on_hover_text("Move up").clicked() {
+                                action = Some(PresetAction::MoveUp(ii));
+                            }
+                            if ui.add_enabled(ii + 1 < count, Button::new("⏷")).on_hover_text("Move down").clicked() {
+                                action = Some(PresetAction::MoveDown(ii));
+                            }
+                            if ui.button("Duplicate").clicked() {
+                                action = Some(PresetAction::Duplicate(ii));
+                            }
+                            if ui.button("Delete").clicked() {
+                                action = Some(PresetAction::Delete(ii));
+                            }
+                            ui.end_row();
+                        }
+                    });
+                });
+            });
+            
+            // Indices of the default and selected presets follow the preset they point to
+            let mut default_preset = self.default_preset;
+            let mut selected_preset = self.next_selected_preset.map(|p| p as usize);
+            let mut remap = |f: &dyn Fn(usize) -> Option<usize>| {
+                default_preset = default_preset.and_then(f);
+                selected_preset = selected_preset.and_then(f);
+            };
+            match action {
+                Some(PresetAction::MoveUp(ii)) => {
+                    self.presets.swap(ii - 1, ii);
+                    remap(&|p| Some(if p == ii {ii - 1} else if p == ii - 1 {ii} else {p}));
+                },
+                Some(PresetAction::MoveDown(ii)) => {
+                    self.presets.swap(ii, ii + 1);
+                    remap(&|p| Some(if p == ii {ii + 1} else if p == ii + 1 {ii} else {p}));
+                },
+                Some(PresetAction::Duplicate(ii)) => {
+                    let mut preset = self.presets[ii].clone();
+                    preset.name = format!("{} (copy)", preset.name);
+                    self.presets.insert(ii + 1, preset);
+                    remap(&|p| Some(if p > ii {p + 1} else {p}));
+                },
+                Some(PresetAction::Delete(ii)) => {
+                    self.presets.remove(ii);
+                    remap(&|p| if p == ii {None} else if p > ii {Some(p - 1)} else {Some(p)});
+                },
+                None => {},
+            }
+            if action.is_some() {
+                self.default_preset = default_preset;
+                self.next_selected_preset = selected_preset.map(|p| p as u32);
+                self.presets_dirty = true;
+            }
+        }
+        self.presets_window_enabled = presets_window_enabled;
+        
+        let mut go_to_window_enabled = self.go_to_window_enabled;
+        if go_to_window_enabled {
+            egui::Window::new("Go to")
+                .open(&mut go_to_window_enabled).resizable(false).show(ctx, |ui| {
+                
+                ui.label("Coordinates x, y, z, u, v, w");
+                ui.horizontal(|ui| {
+                    let response = ui.text_edit_singleline(&mut self.go_to_text);
+                    let entered = response.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter));
+                    if ui.button("Go").clicked() || entered {
+                        match parse_coords(&self.go_to_text, self.current_initial_settings.size) {
+                            Ok(coords) => {
+                                self.go_to_error = None;
+                                self.go_to(coords);
+                            },
+                            Err(error) => self.go_to_error = Some(error),
+                        }
+                    }
+                });
+                if let Some(error) = &self.go_to_error {
+                    ui.colored_label(Color32::RED, error);
+                }
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui.button("Next number with mines left").clicked() {
+                        self.find_next(CellQuery::OpenNumber);
+                    }
+                    if ui.button("Next cell next to a solved number").clicked() {
+                        self.find_next(CellQuery::SafeCell);
+                    }
+                });
+                if let Some(status) = self.find_status {
+                    ui.label(status);
+                }
+            });
+        }
+        self.go_to_window_enabled = go_to_window_enabled;
+        
+        let mut layout_window_enabled = self.layout_window_enabled;
+        if layout_window_enabled {
+            let mut layout = self.layout.clone();
+            egui::Window::new("Layout")
+                .open(&mut layout_window_enabled).show(ctx, |ui| {
+                
+                ui.label(format!("Current layout: {} (axes going across / down, innermost first)", layout));
+                egui::Grid::new("layout_grid").striped(true).show(ui, |ui| {
+                    ui.label("Axis");
+                    ui.label("Direction");
+                    ui.label("Nesting");
+                    ui.end_row();
                     
-                    if let Some(ref mut s) = self.next_initial_settings.seed {
-                        ui.add_enabled(true, egui::TextEdit::singleline(&mut *s));
-                    } else {
-                        ui.add_enabled(false, egui::TextEdit::singleline(&mut ""));
-                    };
+                    for (axis, name) in AXIS_NAMES.iter().enumerate() {
+                        let (direction, level) = layout.position(axis);
+                        let count = if direction == 0 {layout.across.len()} else {layout.down.len()};
+                        ui.label(format!("{} ({} cells)", name, self.current_initial_settings.size[axis]));
+                        ui.horizontal(|ui| {
+                            for (target, text) in [(0, "Across"), (1, "Down")] {
+                                if ui.radio(direction == target, text).clicked() && direction != target {
+                                    layout.set_direction(axis, target);
+                                }
+                            }
+                        });
+                        ui.label(format!("{} of {}", level + 1, count));
+                        if ui.add_enabled(level > 0, Button::new("Inner")).on_hover_text("Nest inside the previous axis").clicked() {
+                            layout.move_axis(axis, false);
+                        }
+                        if ui.add_enabled(level + 1 < count, Button::new("Outer")).on_hover_text("Nest around the next axis").clicked() {
+                            layout.move_axis(axis, true);
+                        }
+                        ui.end_row();
+                    }
                 });
-                
-                ui.with_layout(egui::Layout::left_to_right(egui::Align::TOP), |ui| {
-                    if ui.button("Reset").clicked() {
-                        self.next_initial_settings = self.current_initial_settings.clone();
+                ui.horizontal(|ui| {
+                    if ui.button("Swap across and down").clicked() {
+                        layout = layout.transposed();
                     }
-                    if ui.button("Start").clicked() {
-                        self.current_initial_settings = self.next_initial_settings.clone();
-                        self.new_game_window_enabled = false;
-                        self.reset();
+                    if ui.button("Default").clicked() {
+                        layout = BoardLayout::default();
+                    }
+                    if ui.checkbox(&mut self.auto_layout, "Auto layout")
+                        .on_hover_text("Pick the layout that fits the window best").changed() {
+                        self.preferences_dirty = true;
                     }
                 });
             });
+            if layout != self.layout {
+                self.set_layout(layout, ctx.screen_rect().max);
+            }
+        }
+        self.layout_window_enabled = layout_window_enabled;
+        
+        let mut slice_view = self.slice_view;
+        if slice_view {
+            let mut slice = self.slice.clone();
+            let size = self.current_initial_settings.size;
+            egui::Window::new("Slice explorer")
+                .open(&mut slice_view).show(ctx, |ui| {
+                
+                let name = |axis: Option<usize>| axis.map_or("none".to_string(), |a| AXIS_NAMES[a].to_string());
+                egui::Grid::new("slice_grid").show(ui, |ui| {
+                    for (slot, text) in [(0, "Across"), (1, "Down"), (2, "Layers")] {
+                        let current = [Some(slice.across), Some(slice.down), slice.layers][slot];
+                        // Layers can be any axis that isn't shown yet
+                        let choices: Vec<Option<usize>> = if slot == 2 {
+                            [None].into_iter().chain((0..DIMENSIONS_COUNT).filter(|&a| !slice.shows(a)).map(Some)).collect()
+                        } else {
+                            (0..DIMENSIONS_COUNT).map(Some).collect()
+                        };
+                        ui.label(text);
+                        egui::ComboBox::from_id_source(("slice_axis", slot))
+                            .selected_text(name(current))
+                            .show_ui(ui, |ui| {
+                            for axis in choices {
+                                if ui.selectable_label(axis == current, name(axis)).clicked() {
+                                    slice.show(slot, axis);
+                                }
+                            }
+                        });
+                        ui.end_row();
+                    }
+                });
+                ui.separator();
+                
+                // Coordinates of the fixed axes, also follow the keyboard cursor
+                let fixed_axes: Vec<usize> = (0..DIMENSIONS_COUNT).filter(|&a| !slice.shows(a) && size[a] > 1).collect();
+                for axis in fixed_axes {
+                    ui.add(egui::Slider::new(&mut slice.fixed[axis], 0..=size[axis] - 1).text(name(Some(axis))));
+                }
+                ui.checkbox(&mut slice.previews, "Show neighbouring slices");
+            });
+            if slice != self.slice {
+                let reshaped = (slice.across, slice.down, slice.layers, slice.previews)
+                               != (self.slice.across, self.slice.down, self.slice.layers, self.slice.previews);
+                self.slice = slice;
+                if reshaped {
+                    self.zoom_to_fit(ctx.screen_rect().max);
+                }
+            }
+        }
+        if self.slice_view && !slice_view {
+            self.toggle_slice_view(ctx.screen_rect().max);
+        }
+        
+        let mut show_minimap = self.show_minimap;
+        if show_minimap {
+            let geometry = self.geometry();
+            let extent = geometry.extent();
+            let scale = MINIMAP_SIZE / extent[0].max(extent[1]);
+            // Zoom and numbers aren't shown on the minimap
+            let key = BoardCacheKey { zoom_factor: 0.0, show_delta: false, ..self.board_cache_key(ctx) };
+            if self.minimap.as_ref().is_none_or(|(cached, _)| *cached != key) {
+                let image = self.minimap_image(&geometry, scale * ctx.pixels_per_point());
+                self.minimap = Some((key, ctx.load_texture("minimap", image, TextureOptions::NEAREST)));
+            }
+            let mut window = egui::Window::new("Minimap").open(&mut show_minimap).resizable(false);
+            if self.minimap_docked {
+                window = window.anchor(Align2::LEFT_BOTTOM, [16.0, -16.0]);
+            }
+            window.show(ctx, |ui| {
+                let (rect, response) = ui.allocate_exact_size(egui::vec2(extent[0], extent[1]) * scale,
+                                                              Sense::click_and_drag());
+                let painter = ui.painter_at(rect);
+                if let Some((_, texture)) = &self.minimap {
+                    painter.image(texture.id(), rect, Rect::from_min_max(Pos2::ZERO, Pos2::new(1.0, 1.0)),
+                                  Color32::WHITE);
+                }
+                // Part of the board in view
+                let to_minimap = |pos: Pos2| rect.min + (pos - self.view_origin) / self.zoom_factor * scale;
+                painter.rect_stroke(Rect::from_min_max(to_minimap(self.board_rect.min), to_minimap(self.board_rect.max)),
+                                    Rounding::ZERO, Stroke::new(1.5, self.selection_color));
+                // Clicking or dragging centers the view on that place
+                if let (true, Some(pos)) = (response.clicked() || response.dragged(), response.interact_pointer_pos()) {
+                    let point = (pos - rect.min) / scale;
+                    self.view_origin = self.board_rect.center() - point * self.zoom_factor;
+                }
+                ui.checkbox(&mut self.minimap_docked, "Docked");
+            });
+        }
+        if show_minimap != self.show_minimap {
+            self.show_minimap = show_minimap;
+            self.preferences_dirty = true;
         }
-        self.new_game_window_enabled = self.new_game_window_enabled && new_game_window_enabled;
         
         let mut rules_window_enabled = self.rules_window_enabled;
         if rules_window_enabled {
@@ -596,19 +1700,113 @@ The twist is that in n dimensions, every cell has up to 3^n-1 neighbors (e.g. 8
         self.rules_window_enabled = rules_window_enabled;
         let mut controls_window_enabled = self.controls_window_enabled;
         if controls_window_enabled {
+            let mut shortcuts_changed = false;
             egui::Window::new("Controls")
                 .open(&mut controls_window_enabled).show(ctx, |ui| {
-               ui.label(
-r"Currently there are two tools: Probe/Mark and Highlighter.
+                let shortcut = |action: usize| ctx.format_shortcut(&self.shortcuts.bindings[action]);
+                let groups = (0..8).map(|ii| shortcut(Shortcuts::HIGHLIGHT_GROUP_1 + ii)).join(", ");
+                let movement = ["x", "y", "z", "u", "v", "w"].iter().enumerate().map(|(ii, axis)| format!("{} {}/{}",
+                    axis, shortcut(Shortcuts::CURSOR_MOVE + 2*ii), shortcut(Shortcuts::CURSOR_MOVE + 2*ii + 1))).join(", ");
+                ui.label(format!(
+r"Currently there are two tools: Probe/Mark ({}) and Highlighter ({}).
+
+Probe/Mark probes a cell with primary button (usually Left Mouse Button) and marks a cell as a mine with secondary button (usually Right Mouse Button).
+
+Highlighter highlights with primary button and unhighlights with secondary button. Highlight groups 1 to 8 are toggled with {}. Tools > Highlight groups also clears whole groups and combines two groups into a third (union, intersection or difference). {} fills that third group with the undiscovered neighbors of the hovered cell (or the one under the keyboard cursor).
+
+Camera may be panned through dragging with middle mouse button, zoomed/unzoomed using scroll wheel. {} resets the view, {} zooms to fit the board. {} shows a minimap of the whole board, clicking or dragging it moves the view there without changing the zoom.
 
-Probe/Mark probes a cell with primary button (usually Left Mouse Button) and marks a cell as a mine with secondary button (usually Right Mouse Button)
+{} opens a window for going to given coordinates. {} goes to the next number that still has mines left to flag, {} to the next undiscovered cell next to a number whose mines are all flagged. Both search from the keyboard cursor and count as assists in high scores.
 
-Highlighter highlights with primary button and unhighlights with secondary button.
+Which axes go across and which go down, and how they are nested, is set in View > Layout. {} swaps the axes going across with the ones going down, {} toggles the auto layout, which arranges the axes to fit the window.
 
-Camera may be panned through dragging with middle mouse button, zoomed/unzoomed using scroll wheel.
+{} opens the slice explorer, which shows a single 2D or 3D slice of the board with the neighbouring slices faded around it. Moving the keyboard cursor along a fixed axis goes to the next slice. Neighbors in slices that aren't shown are counted next to the hovered cell.
 
-If neighbor hints are enabled, holding Shift freezes them in place, whereas holding Alt temporarily disables them.");
+If neighbor hints are enabled, holding Shift freezes them in place, whereas holding Alt temporarily disables them.
+
+On touch screens a tap probes and holding a finger on a cell marks it (the button in the corner swaps the two). Two fingers pan the board, pinching zooms.
+
+The board can also be played with a keyboard cursor, moved along the axes with {}. {} probes the cell under it, {} marks it, {} chords (probes the neighbors of a number whose mines are all marked) and {} toggles the selected highlight groups.",
+                    shortcut(Shortcuts::PROBE_MARK), shortcut(Shortcuts::HIGHLIGHTER), groups,
+                    shortcut(Shortcuts::HIGHLIGHT_NEIGHBORS),
+                    shortcut(Shortcuts::RESET_VIEW), shortcut(Shortcuts::ZOOM_TO_FIT), shortcut(Shortcuts::MINIMAP),
+                    shortcut(Shortcuts::GO_TO), shortcut(Shortcuts::FIND_OPEN_NUMBER), shortcut(Shortcuts::FIND_SAFE_CELL),
+                    shortcut(Shortcuts::TRANSPOSE_LAYOUT), shortcut(Shortcuts::AUTO_LAYOUT),
+                    shortcut(Shortcuts::SLICE_VIEW), movement,
+                    shortcut(Shortcuts::CURSOR_PROBE), shortcut(Shortcuts::CURSOR_MARK),
+                    shortcut(Shortcuts::CURSOR_CHORD), shortcut(Shortcuts::CURSOR_HIGHLIGHT)));
+                
+                ui.separator();
+                ui.heading("Keyboard shortcuts");
+                
+                // Next key pressed (with its modifiers) becomes the binding, Escape cancels
+                let mut new_binding = None;
+                if let Some(action) = self.capturing_shortcut {
+                    let pressed = ctx.input(|i| i.events.iter().find_map(|e| match e {
+                        egui::Event::Key { key, pressed: true, modifiers, .. } => Some((*key, *modifiers)),
+                        _ => None,
+                    }));
+                    if let Some((key, modifiers)) = pressed {
+                        ctx.input_mut(|i| i.consume_key(modifiers, key));
+                        self.capturing_shortcut = None;
+                        self.shortcut_conflict = None;
+                        if key != Key::Escape {
+                            // Same form as if it was loaded from config.toml
+                            let pressed = from_keyboard_shortcut(&KeyboardShortcut::new(modifiers, key));
+                            new_binding = to_keyboard_shortcut(&pressed).map(|b| (action, b));
+                        }
+                    }
+                }
+                
+                let defaults = Shortcuts::default();
+                egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                    egui::Grid::new("shortcuts_grid").striped(true).show(ui, |ui| {
+                        for (ii, (_, description)) in SHORTCUT_ACTIONS.iter().enumerate() {
+                            ui.label(*description);
+                            let text = if self.capturing_shortcut == Some(ii) {
+                                "Press a key…".into()
+                            } else {
+                                ctx.format_shortcut(&self.shortcuts.bindings[ii])
+                            };
+                            let button = Button::new(text).selected(self.capturing_shortcut == Some(ii));
+                            if ui.add(button).on_hover_text("Click, then press the new shortcut").clicked() {
+                                self.capturing_shortcut = Some(ii);
+                                self.shortcut_conflict = None;
+                            }
+                            let default = defaults.bindings[ii];
+                            let reset_button = Button::new(format!("Reset to {}", ctx.format_shortcut(&default)));
+                            if ui.add_enabled(self.shortcuts.bindings[ii] != default, reset_button).clicked() {
+                                new_binding = Some((ii, default));
+                            }
+                            ui.end_row();
+                        }
+                    });
+                });
+                
+                // Bindings used by another action are refused
+                if let Some((action, binding)) = new_binding {
+                    match self.shortcuts.conflict(action, &binding) {
+                        Some(other) => {
+                            self.shortcut_conflict = Some(format!("{} is already used for {}",
+                                ctx.format_shortcut(&binding), SHORTCUT_ACTIONS[other].1));
+                        },
+                        None => {
+                            self.shortcuts.bindings[action] = binding;
+                            shortcuts_changed = true;
+                        },
+                    }
+                }
+                if let Some(conflict) = &self.shortcut_conflict {
+                    ui.colored_label(Color32::RED, conflict);
+                }
             });
+            if shortcuts_changed {
+                self.save_shortcuts(frame);
+            }
+        }
+        if !controls_window_enabled {
+            self.capturing_shortcut = None;
+            self.shortcut_conflict = None;
         }
         self.controls_window_enabled = controls_window_enabled;
         let mut about_window_enabled = self.about_window_enabled;
@@ -631,6 +1829,189 @@ If neighbor hints are enabled, holding Shift freezes them in place, whereas hold
             });
         }
         self.about_window_enabled = about_window_enabled;
+        let mut post_mortem_window_enabled = self.post_mortem_window_enabled;
+        if let (true, Some(post_mortem)) = (post_mortem_window_enabled, &self.post_mortem) {
+            egui::Window::new("Post-mortem")
+                .open(&mut post_mortem_window_enabled).show(ctx, |ui| {
+                
+                if let Some(fatal_cell) = post_mortem.fatal_cell {
+                    ui.label(format!("Fatal probe at {}", format_coords(fatal_cell)));
+                }
+                ui.label(match post_mortem.verdict {
+                    ProbeVerdict::ForcedGuess
+                        => "It was a forced guess: no cell could have been proven safe.",
+                    ProbeVerdict::AvoidableGuess
+                        => "It was a guess, but other cells could have been proven safe.",
+                    ProbeVerdict::DeducibleMistake
+                        => "It was a deducible mistake: the cell was provably a mine.",
+                });
+                if let Some(p) = post_mortem.fatal_probability {
+                    ui.label(format!("Mine probability when probed: {}{:.1} %",
+                                     if post_mortem.probability_exact {""} else {"≈ "}, p * 100.0));
+                }
+                ui.separator();
+                
+                egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                    ui.colored_label(self.wrong_flag_color,
+                                     format!("Wrongly placed flags ({}):", post_mortem.wrong_flags.len()));
+                    for coords in &post_mortem.wrong_flags {
+                        ui.label(format_coords(*coords));
+                    }
+                    ui.colored_label(self.missed_mine_color,
+                                     format!("Missed mines ({}):", post_mortem.missed_mines.len()));
+                    for coords in &post_mortem.missed_mines {
+                        ui.label(format_coords(*coords));
+                    }
+                });
+            });
+        }
+        self.post_mortem_window_enabled = post_mortem_window_enabled;
+        let mut statistics_window_enabled = self.statistics_window_enabled;
+        if statistics_window_enabled {
+            egui::Window::new("Statistics")
+                .open(&mut statistics_window_enabled).show(ctx, |ui| {
+                
+                if self.statistics.presets.is_empty() {
+                    ui.label("No finished games yet.");
+                }
+                egui::ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
+                    egui::Grid::new("statistics_grid").striped(true).show(ui, |ui| {
+                        for header in ["Preset", "Played", "Win rate", "Streak", "Best streak",
+                                       "Best time", "Average time", "Best 3BV/s"] {
+                            ui.strong(header);
+                        }
+                        ui.end_row();
+                        
+                        for (name, stats) in &self.statistics.presets {
+                            ui.label(name);
+                            ui.label(format!("{}", stats.played));
+                            ui.label(format!("{:.1} %", stats.win_rate() * 100.0));
+                            ui.label(format!("{}", stats.current_streak));
+                            ui.label(format!("{}", stats.best_streak));
+                            ui.label(stats.best_time_ms.map_or("-".into(),
+                                                               |t| Duration::from_millis(t).hhmmssxxx()));
+                            ui.label(stats.average_time_ms().map_or("-".into(),
+                                                                    |t| Duration::from_millis(t).hhmmssxxx()));
+                            ui.label(stats.best_bbbv_per_second.map_or("-".into(), |b| format!("{:.2}", b)));
+                            ui.end_row();
+                        }
+                    });
+                });
+                
+                if ui.button("Reset statistics").clicked() {
+                    self.statistics = Statistics::default();
+                    self.statistics_dirty = true;
+                }
+            });
+        }
+        self.statistics_window_enabled = statistics_window_enabled;
+        let mut high_scores_window_enabled = self.high_scores_window_enabled;
+        if high_scores_window_enabled {
+            let mut to_open = None;
+            egui::Window::new("High scores")
+                .open(&mut high_scores_window_enabled).show(ctx, |ui| {
+                
+                if self.statistics.high_scores.is_empty() {
+                    ui.label("No games won yet.");
+                }
+                egui::ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
+                    for (name, table) in &self.statistics.high_scores {
+                        egui::CollapsingHeader::new(name).default_open(true).show(ui, |ui| {
+                            egui::Grid::new(format!("high_scores_grid_{}", name)).striped(true).show(ui, |ui| {
+                                for header in ["#", "Date", "Time", "3BV", "Seed", "Assists", ""] {
+                                    ui.strong(header);
+                                }
+                                ui.end_row();
+                                
+                                for (ii, high_score) in table.iter().enumerate() {
+                                    ui.label(format!("{}", ii + 1));
+                                    ui.label(chrono::DateTime::from_timestamp(high_score.date as i64, 0)
+                                                .map_or("-".into(), |d| d.format("%Y-%m-%d %H:%M").to_string()));
+                                    ui.label(Duration::from_millis(high_score.time_ms).hhmmssxxx());
+                                    ui.label(format!("{}", high_score.bbbv));
+                                    ui.label(format!("{:016x}", high_score.seed));
+                                    ui.label(if high_score.assists {"yes"} else {"no"});
+                                    ui.horizontal(|ui| {
+                                        if ui.button("Play board").clicked() {
+                                            to_open = Some((name.clone(), high_score.clone(), false));
+                                        }
+                                        if ui.add_enabled(!high_score.replay.is_empty(), Button::new("Watch replay"))
+                                             .clicked() {
+                                            to_open = Some((name.clone(), high_score.clone(), true));
+                                        }
+                                    });
+                                    ui.end_row();
+                                }
+                            });
+                        });
+                    }
+                });
+            });
+            if let Some((name, high_score, watch)) = to_open {
+                self.open_high_score(&name, &high_score, watch);
+            }
+        }
+        self.high_scores_window_enabled = high_scores_window_enabled;
+        let mut config_problems_window_enabled = self.config_problems_window_enabled;
+        if config_problems_window_enabled {
+            egui::Window::new("Configuration problems")
+                .open(&mut config_problems_window_enabled).show(ctx, |ui| {
+                
+                ui.label(match &self.config_path {
+                    Some(path) => format!("Problems in {}:", path.display()),
+                    None => "Problems in the configuration:".into(),
+                });
+                ui.label("Values with problems were ignored or adjusted, defaults are used instead.");
+                egui::ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
+                    for problem in &self.config_problems {
+                        let color = if problem.severity == Severity::Error {Color32::RED} else {Color32::GOLD};
+                        ui.colored_label(color, problem.to_string());
+                    }
+                });
+            });
+        }
+        self.config_problems_window_enabled = config_problems_window_enabled;
+        let mut config_window_enabled = self.config_window_enabled;
+        if config_window_enabled {
+            let mut apply = false;
+            egui::Window::new("Configuration")
+                .open(&mut config_window_enabled).show(ctx, |ui| {
+                
+                ui.label(match &self.config_path {
+                    Some(path) => format!("Saved in {}", path.display()),
+                    None => "Saved in the browser".into(),
+                });
+                ui.label("Only values that differ from the built-in configuration are needed. \
+                          Paste a configuration here to import it, copy it to export it.");
+                egui::ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
+                    ui.add(egui::TextEdit::multiline(&mut self.config_editor_text)
+                               .code_editor()
+                               .desired_width(f32::INFINITY));
+                });
+                ui.horizontal(|ui| {
+                    if ui.button("Apply and save").clicked() {
+                        apply = true;
+                    }
+                    if ui.button("Copy to clipboard").clicked() {
+                        ui.output_mut(|o| o.copied_text = self.config_editor_text.clone());
+                    }
+                    if ui.button("Revert").clicked() {
+                        self.config_editor_text = self.user_config_text.clone();
+                    }
+                    if ui.button("Built-in configuration").clicked() {
+                        self.config_editor_text = DEFAULT_CONFIG.into();
+                    }
+                });
+            });
+            if apply {
+                let (config, problems) = Config::layered(&self.config_editor_text);
+                self.config_problems = problems;
+                self.apply_config(config);
+                self.config_problems_window_enabled = !self.config_problems.is_empty();
+                self.store_user_config(frame, self.config_editor_text.clone());
+            }
+        }
+        self.config_window_enabled = config_window_enabled;
         
         TopBottomPanel::top("menubar_panel")
             .frame(egui::Frame::none().fill(egui::Color32::LIGHT_BLUE))
@@ -644,16 +2025,45 @@ If neighbor hints are enabled, holding Shift freezes them in place, whereas hold
                             self.new_game_window_enabled = !self.new_game_window_enabled;
                             ui.close_menu();
                         }
+                        let presets_button = Button::new("Presets").selected(self.presets_window_enabled);
+                        if ui.add(presets_button).clicked() {
+                            self.presets_window_enabled = !self.presets_window_enabled;
+                            ui.close_menu();
+                        }
                         if ui.button("Quick restart").clicked() {
                             self.reset();
                             ui.close_menu();
                         }
+                        let statistics_button = Button::new("Statistics")
+                                                    .selected(self.statistics_window_enabled);
+                        if ui.add(statistics_button).clicked() {
+                            self.statistics_window_enabled = !self.statistics_window_enabled;
+                            ui.close_menu();
+                        }
+                        let high_scores_button = Button::new("High scores")
+                                                    .selected(self.high_scores_window_enabled);
+                        if ui.add(high_scores_button).clicked() {
+                            self.high_scores_window_enabled = !self.high_scores_window_enabled;
+                            ui.close_menu();
+                        }
+                        let config_button = Button::new("Configuration").selected(self.config_window_enabled);
+                        if ui.add(config_button).clicked() {
+                            self.config_window_enabled = !self.config_window_enabled;
+                            self.config_editor_text = self.user_config_text.clone();
+                            ui.close_menu();
+                        }
+                        let post_mortem_button = Button::new("Post-mortem")
+                                                    .selected(self.post_mortem_window_enabled);
+                        if ui.add_enabled(self.post_mortem.is_some(), post_mortem_button).clicked() {
+                            self.post_mortem_window_enabled = !self.post_mortem_window_enabled;
+                            ui.close_menu();
+                        }
                     });
                     ui.menu_button("View", |ui| {
                         let _ = ui.button(format!("Current zoom: {:.3} %", self.zoom_factor*100.0));
                         let reset_view_button = Button::new("Reset to 0x0 @ 100%")
                             .shortcut_text(
-                                RichText::new(ctx.format_shortcut(&self.shortcuts.reset_view_shortcut))
+                                RichText::new(ctx.format_shortcut(&self.shortcuts.bindings[Shortcuts::RESET_VIEW]))
                                     .color(Color32::WHITE));
                         if ui.add(reset_view_button).clicked() {
                             self.reset_view();
@@ -661,28 +2071,95 @@ If neighbor hints are enabled, holding Shift freezes them in place, whereas hold
                         }
                         let zoom_to_fit_button = Button::new("Zoom to fit")
                             .shortcut_text(
-                                RichText::new(ctx.format_shortcut(&self.shortcuts.zoom_to_fit_shortcut))
+                                RichText::new(ctx.format_shortcut(&self.shortcuts.bindings[Shortcuts::ZOOM_TO_FIT]))
                                     .color(Color32::WHITE));
                         if ui.add(zoom_to_fit_button).clicked() {
                             self.zoom_to_fit(ctx.screen_rect().max);
                             ui.close_menu();
                         }
+                        let go_to_button = Button::new("Go to")
+                            .selected(self.go_to_window_enabled)
+                            .shortcut_text(
+                                RichText::new(ctx.format_shortcut(&self.shortcuts.bindings[Shortcuts::GO_TO]))
+                                    .color(Color32::WHITE));
+                        if ui.add(go_to_button).clicked() {
+                            self.go_to_window_enabled = !self.go_to_window_enabled;
+                            ui.close_menu();
+                        }
+                        for (query, text, action) in [
+                            (CellQuery::OpenNumber, "Find next number with mines left", Shortcuts::FIND_OPEN_NUMBER),
+                            (CellQuery::SafeCell, "Find next cell next to a solved number", Shortcuts::FIND_SAFE_CELL),
+                        ] {
+                            let find_button = Button::new(text)
+                                .shortcut_text(
+                                    RichText::new(ctx.format_shortcut(&self.shortcuts.bindings[action]))
+                                        .color(Color32::WHITE));
+                            if ui.add(find_button).clicked() {
+                                self.find_next(query);
+                                ui.close_menu();
+                            }
+                        }
+                        let layout_button = Button::new("Layout").selected(self.layout_window_enabled);
+                        if ui.add(layout_button).clicked() {
+                            self.layout_window_enabled = !self.layout_window_enabled;
+                            ui.close_menu();
+                        }
+                        let transpose_layout_button = Button::new("Swap across and down axes")
+                            .shortcut_text(
+                                RichText::new(ctx.format_shortcut(&self.shortcuts.bindings[Shortcuts::TRANSPOSE_LAYOUT]))
+                                    .color(Color32::WHITE));
+                        if ui.add(transpose_layout_button).clicked() {
+                            self.set_layout(self.layout.transposed(), ctx.screen_rect().max);
+                            ui.close_menu();
+                        }
+                        let slice_view_button = Button::new("Slice explorer")
+                            .selected(self.slice_view)
+                            .shortcut_text(
+                                RichText::new(ctx.format_shortcut(&self.shortcuts.bindings[Shortcuts::SLICE_VIEW]))
+                                    .color(Color32::WHITE));
+                        if ui.add(slice_view_button).clicked() {
+                            self.toggle_slice_view(ctx.screen_rect().max);
+                            ui.close_menu();
+                        }
+                        let minimap_button = Button::new("Minimap")
+                            .selected(self.show_minimap)
+                            .shortcut_text(
+                                RichText::new(ctx.format_shortcut(&self.shortcuts.bindings[Shortcuts::MINIMAP]))
+                                    .color(Color32::WHITE));
+                        if ui.add(minimap_button).clicked() {
+                            self.show_minimap = !self.show_minimap;
+                            self.preferences_dirty = true;
+                            ui.close_menu();
+                        }
+                        let auto_layout_button = Button::new("Auto layout")
+                            .selected(self.auto_layout)
+                            .shortcut_text(
+                                RichText::new(ctx.format_shortcut(&self.shortcuts.bindings[Shortcuts::AUTO_LAYOUT]))
+                                    .color(Color32::WHITE));
+                        if ui.add(auto_layout_button).clicked() {
+                            self.auto_layout = !self.auto_layout;
+                            self.preferences_dirty = true;
+                            ui.close_menu();
+                        }
                         let show_neighbors_button = Button::new("Show neighbors")
                                                     .selected(self.show_neighbors);
                         if ui.add(show_neighbors_button).clicked() {
                             self.show_neighbors = !self.show_neighbors;
+                            self.preferences_dirty = true;
                             ui.close_menu();
                         }
                         let unlimited_zoom_button = Button::new("Unlimited zoom")
                                                     .selected(self.unlimited_zoom);
                         if ui.add(unlimited_zoom_button).clicked() {
                             self.unlimited_zoom = !self.unlimited_zoom;
+                            self.preferences_dirty = true;
                             ui.close_menu();
                         }
                         let show_timer_miliseconds_button = Button::new("Show timer miliseconds")
                                                     .selected(self.show_timer_miliseconds);
                         if ui.add(show_timer_miliseconds_button).clicked() {
                             self.show_timer_miliseconds = !self.show_timer_miliseconds;
+                            self.preferences_dirty = true;
                             ui.close_menu();
                         }
                     });
@@ -692,13 +2169,13 @@ If neighbor hints are enabled, holding Shift freezes them in place, whereas hold
                         let probe_and_mark_button = Button::new("Probe/Mark")
                             .selected(self.cursor_mode == CursorMode::ProbeAndMark)
                             .shortcut_text(
-                                RichText::new(ctx.format_shortcut(&self.shortcuts.probe_mark_shortcut))
+                                RichText::new(ctx.format_shortcut(&self.shortcuts.bindings[Shortcuts::PROBE_MARK]))
                                     .color(Color32::WHITE));
                         
                         let highlight_button = Button::new("Highlighter")
                              .selected(if self.cursor_mode == CursorMode::Highlighter {true} else {false})
                              .shortcut_text(
-                                RichText::new(ctx.format_shortcut(&self.shortcuts.highlighter_shortcut))
+                                RichText::new(ctx.format_shortcut(&self.shortcuts.bindings[Shortcuts::HIGHLIGHTER]))
                                     .color(Color32::WHITE));
                         
                         if ui.add(probe_and_mark_button).clicked() {
@@ -709,6 +2186,7 @@ If neighbor hints are enabled, holding Shift freezes them in place, whereas hold
                             let probe_marked = Button::new("Allow probing marked cells").selected(self.probe_marked);
                             if ui.add(probe_marked).clicked() {
                                 self.probe_marked = !self.probe_marked;
+                                self.preferences_dirty = true;
                             }
                         });
                         
@@ -724,10 +2202,61 @@ If neighbor hints are enabled, holding Shift freezes them in place, whereas hold
                                                if (self.selected_highlighters & (1 << ii)) > 0 {"on"} else {"off"}))
                                         .selected((self.selected_highlighters & (1 << ii)) > 0)
                                         .stroke(Stroke::new(2.0, self.highlight_colors[ii]))
-                                        .shortcut_text(ctx.format_shortcut(&self.shortcuts.highlight_group_shortcuts[ii]));
+                                        .shortcut_text(ctx.format_shortcut(
+                                            &self.shortcuts.bindings[Shortcuts::HIGHLIGHT_GROUP_1 + ii]));
                                 
-                                if ui.add(highlight_group_button).clicked() {
-                                    self.selected_highlighters ^= 1 << ii;
+                                ui.horizontal(|ui| {
+                                    if ui.color_edit_button_srgba(&mut self.highlight_colors[ii]).changed() {
+                                        self.preferences_dirty = true;
+                                    }
+                                    if ui.add(highlight_group_button).clicked() {
+                                        self.selected_highlighters ^= 1 << ii;
+                                    }
+                                });
+                            }
+                            
+                            // Whole groups at once, the target group is replaced with the result
+                            ui.separator();
+                            let editable = self.replay_playback.is_none();
+                            let [first, second, target] = self.highlight_operands.map(|g| g - 1);
+                            let cursor = self.keyboard_cursor();
+                            if let (Some(game), true) = (&mut self.game, editable) {
+                                ui.menu_button("Clear group", |ui| {
+                                    for ii in 0..8 {
+                                        if ui.button(format!("Group {}", ii+1)).clicked() {
+                                            game.clear_highlight_group(ii);
+                                            ui.close_menu();
+                                        }
+                                    }
+                                });
+                                ui.horizontal(|ui| {
+                                    ui.label("Group");
+                                    ui.add(egui::DragValue::new(&mut self.highlight_operands[2]).clamp_range(1..=8));
+                                    ui.label("=");
+                                    ui.add(egui::DragValue::new(&mut self.highlight_operands[0]).clamp_range(1..=8));
+                                    for (operation, symbol, text) in [
+                                        (HighlightOperation::Union, "∪", "Union: cells in either group"),
+                                        (HighlightOperation::Intersection, "∩", "Intersection: cells in both groups"),
+                                        (HighlightOperation::Difference, "∖", "Difference: cells in the first group only"),
+                                    ] {
+                                        if ui.button(symbol).on_hover_text(text).clicked() {
+                                            game.combine_highlight_groups(operation, first, second, target);
+                                        }
+                                    }
+                                    ui.add(egui::DragValue::new(&mut self.highlight_operands[1]).clamp_range(1..=8));
+                                });
+                                // Neighborhoods are picked by the keyboard cursor, or by hovering with the shortcut
+                                let neighbors_button = Button::new(format!("Group {} = undiscovered neighbors of cursor",
+                                                                           target + 1))
+                                    .shortcut_text(
+                                        RichText::new(ctx.format_shortcut(
+                                            &self.shortcuts.bindings[Shortcuts::HIGHLIGHT_NEIGHBORS]))
+                                            .color(Color32::WHITE));
+                                if ui.add_enabled(cursor.is_some(), neighbors_button).clicked() {
+                                    if let Some(coords) = cursor {
+                                        game.highlight_undiscovered_neighbors(coords, target);
+                                    }
+                                    ui.close_menu();
                                 }
                             }
                         });
@@ -748,9 +2277,17 @@ If neighbor hints are enabled, holding Shift freezes them in place, whereas hold
                             self.about_window_enabled = !self.about_window_enabled;
                             ui.close_menu();
                         }
+                        let config_problems_button
+                            = Button::new(format!("Configuration problems ({})", self.config_problems.len()))
+                                .selected(self.config_problems_window_enabled);
+                        if ui.add_enabled(!self.config_problems.is_empty(), config_problems_button).clicked() {
+                            self.config_problems_window_enabled = !self.config_problems_window_enabled;
+                            ui.close_menu();
+                        }
                     });
                     if ui.button(format!("Δ: {}", if self.show_delta {"yes"} else {"no"})).clicked() {
                         self.show_delta = !self.show_delta;
+                        self.preferences_dirty = true;
                     }
                     if let Some(game) = &self.game {
                         let seed = format!("seed: {:016x}", game.seed());
@@ -760,20 +2297,29 @@ If neighbor hints are enabled, holding Shift freezes them in place, whereas hold
                         ui.button(format!("({}/{})  {}",
                                   if let Some(game) = &self.game {game.marked_as_mine()} else {0},
                                   self.current_initial_settings.mines,
-                                  (0..DIMENSIONS_COUNT).map(
-                                    |i| format!("{}{}",
-                                        self.current_initial_settings.size[i],
-                                        if self.current_initial_settings.wrap[i] {"w"} else {""})
-                                  ).join(" x ")))
+                                  self.current_initial_settings.size_description()))
                     });
                 });
             });
         
+        let inspected = self.hovered_cell(ctx).or(self.keyboard_cursor());
         TopBottomPanel::bottom("bottom_panel")
             .frame(egui::Frame::none().fill(egui::Color32::LIGHT_BLUE))
             .show(ctx, |ui| {
                 ui.visuals_mut().override_text_color = Some(egui::Color32::BLACK);
+                // Always there, so that the board doesn't move when the pointer leaves it
+                ui.label(match (&self.game, inspected) {
+                    (Some(game), Some(coords)) => self.inspector_text(game, coords),
+                    _ => "Hover a cell to inspect it".into(),
+                });
                 menu::bar(ui, |ui| {
+                    if let Some(playback) = &self.replay_playback {
+                        let _ = ui.button(format!("Watching replay: {}/{} actions",
+                                                  playback.next, playback.actions.len()));
+                    }
+                    if let Some(status) = self.find_status {
+                        let _ = ui.button(status);
+                    }
                     match self.cursor_mode {
                         CursorMode::ProbeAndMark => {
                             let _ = ui.button("Probe/Mark: primary to probe a cell, secondary to mark as a mine");
@@ -800,161 +2346,76 @@ If neighbor hints are enabled, holding Shift freezes them in place, whereas hold
                                                                     _ => ""
                                                                  }, fdur
                                 ));
+                                // After a loss the rates use the 3BV actually cleared, shown as cleared/total
+                                if game.state() != GameState::Running {
+                                    let cleared = game.bbbv_cleared();
+                                    let bbbv = if game.state() == GameState::Victory
+                                                { cleared.to_string() } else { format!("{}/{}", cleared, game.bbbv()) };
+                                    let clicks = self.left_clicks + self.right_clicks + self.chord_clicks;
+                                    let _ = ui.button(format!("3BV: {}  3BV/s: {:.2}  Clicks: {}+{}+{}  Efficiency: {:.0} %",
+                                                              bbbv,
+                                                              cleared as f32 / dur.as_secs_f32().max(0.001),
+                                                              self.left_clicks, self.right_clicks, self.chord_clicks,
+                                                              cleared as f32 / clicks.max(1) as f32 * 100.0));
+                                }
                             }
                         }
                     });
                 });
             });
         
+        // Auto layout follows the board and the window size
+        if self.auto_layout {
+            let screen_size = ctx.screen_rect().max;
+            let layout = BoardLayout::auto(self.current_initial_settings.size, self.tile_spacings, self.cell_edge,
+                                           [screen_size.x - 20.0, screen_size.y - 60.0]);
+            if layout != self.layout {
+                self.layout = layout;
+                self.zoom_to_fit(screen_size);
+            }
+        }
+        
+        // Touch screens have no secondary button, taps can be switched to marking instead
+        if self.touch_seen && self.cursor_mode == CursorMode::ProbeAndMark {
+            egui::Area::new("touch_mode_area")
+                .anchor(Align2::RIGHT_BOTTOM, [-16.0, -16.0])
+                .show(ctx, |ui| {
+                let text = if self.flag_mode {"🚩 Flag"} else {"Probe"};
+                let button = Button::new(RichText::new(text).size(28.0)).selected(self.flag_mode)
+                                .min_size(egui::vec2(120.0, 60.0));
+                if ui.add(button).on_hover_text("What a tap does, long press does the other").clicked() {
+                    self.flag_mode = !self.flag_mode;
+                }
+            });
+        }
+
         egui::CentralPanel::default()
         .frame(egui::Frame::none().fill(egui::Color32::GRAY))
         .show(ctx, |ui| {
 
-            let basic_stroke = Stroke::new(2.0 * self.zoom_factor, egui::Color32::BLACK);
-            let harder_stroke = Stroke::new(4.0 * self.zoom_factor, egui::Color32::BLACK);
             let neighbor_stroke = Stroke::new(3.0 * self.zoom_factor, self.neighbor_color);
             let center_stroke = Stroke::new(3.0 * self.zoom_factor, self.center_color);
             let selection_stroke = Stroke::new(3.0 * self.zoom_factor, self.selection_color);
-            let highlight_strokes = self.highlight_colors.map(|x| Stroke::new(2.0 * self.zoom_factor, x));
 
-            let screen_size = ctx.screen_rect().max;
             let [c_xx, c_yy, c_zz, c_uu, c_vv, c_ww] = self.current_initial_settings.size;
+            let geometry = self.geometry();
             
             let (painter_response, painter) = ui.allocate_painter(ui.available_size(), Sense::click_and_drag());
+            self.board_rect = painter_response.rect;
 
-            // Paint cell contents
-            let background_color = Color32::GRAY;
-            if self.zoom_factor > 0.05 {
-                if let Some(game) = &self.game {
-                    for iw in 0..c_ww {
-                    for iv in 0..c_vv {
-                    for iu in 0..c_uu {
-                    for iz in 0..c_zz {
-                    for iy in 0..c_yy {
-                    for ix in 0..c_xx {
-                        let (spc_x, spc_y) = (self.cum_spc_x(ix,iz,iv), self.cum_spc_y(iy,iu,iw));
-                        let ulc = self.sc_tr((ix+iz*c_xx+iv*c_xx*c_zz) as f32 * self.cell_edge + spc_x,
-                                             (iy+iu*c_yy+iw*c_yy*c_uu) as f32 * self.cell_edge + spc_y);
-                        // Only draw symbols reasonably close to the viewport
-                        if ulc.x >= -self.cell_edge*self.zoom_factor && ulc.x <= screen_size.x
-                           && ulc.y >= -self.cell_edge*self.zoom_factor && ulc.y <= screen_size.y {
-                            let (symbol, color) = match game.cell_at([ix, iy, iz, iu, iv, iw]) {
-                                CellState::UndiscoveredMine(_)
-                                    => if game.state() == GameState::Victory {
-                                            ("💣".into(), Color32::GREEN)
-                                       } else if game.state() == GameState::Loss {
-                                            ("💣".into(), Color32::RED)
-                                       } else {
-                                            ("".into(), Color32::GRAY)
-                                       },
-                                CellState::MarkedMine(_)
-                                    => if game.state() == GameState::Victory || game.state() == GameState::Loss {
-                                            ("🚩".into(), Color32::GREEN)
-                                       } else {
-                                            ("🚩".into(), Color32::GRAY)
-                                       },
-                                CellState::ExplodedMine(_) => ("💥".into(), Color32::RED),
-                                CellState::UndiscoveredEmpty(..) => ("".into(), Color32::GRAY),
-                                CellState::MarkedEmpty(..)
-                                    => if game.state() == GameState::Victory || game.state() == GameState::Loss {
-                                            ("🚩".into(), Color32::RED)
-                                       } else {
-                                            ("🚩".into(), Color32::GRAY)
-                                       },
-                                CellState::DiscoveredEmpty(mc, delta, _)
-                                    => (if mc == 0 && delta == 0 {"".into()}
-                                        else {format!("{}", if self.show_delta {delta} else {mc as i32})},
-                                        Color32::LIGHT_GRAY),
-                            };
-                            
-                            // Only paint squares with different color than the current background
-                            if color != background_color {
-                                painter.add(
-                                    Shape::rect_filled(
-                                        Rect::from_min_max(
-                                                ulc,
-                                                self.sc_tr((ix+iz*c_xx+iv*c_xx*c_zz+1) as f32 * self.cell_edge + spc_x,
-                                                           (iy+iu*c_yy+iw*c_yy*c_uu+1) as f32 * self.cell_edge + spc_y)),
-                                        Rounding::ZERO, color
-                                    )
-                                );
-                            }
-                            
-                            if symbol != "" {
-                                // Since drawing text is somewhat expensive, only draw text that can most definitely be read
-                                if self.zoom_factor >= 0.10 {
-                                    painter.text(
-                                        self.sc_tr((ix+iz*c_xx+iv*c_xx*c_zz) as f32 * self.cell_edge+self.cell_edge/2.0 + spc_x,
-                                                   (iy+iu*c_yy+iw*c_yy*c_uu) as f32 * self.cell_edge+self.cell_edge/2.0 + spc_y),
-                                        Align2::CENTER_CENTER,
-                                        symbol,
-                                        FontId::proportional(25.0 * self.zoom_factor),
-                                        Color32::BLACK
-                                    );
-                                } else {
-                                    painter.add(
-                                        Shape::circle_filled(
-                                            self.sc_tr((ix+iz*c_xx+iv*c_xx*c_zz) as f32 * self.cell_edge+self.cell_edge/2.0 + spc_x,
-                                                       (iy+iu*c_yy+iw*c_yy*c_uu) as f32 * self.cell_edge+self.cell_edge/2.0 + spc_y),
-                                            10.0 * self.zoom_factor,
-                                            Color32::GRAY)
-                                    );
-                                }
-                            }
-                        }
-                    }}}}}}
-                }
+            // The board itself only changes with the game, the zoom or the settings, panning just moves it.
+            // Only cells around the visible ones are drawn, the area is extended when the view leaves it.
+            let key = self.board_cache_key(ctx);
+            let visible = Rect::from_min_max(((painter_response.rect.min - self.view_origin) / self.zoom_factor).to_pos2(),
+                                             ((painter_response.rect.max - self.view_origin) / self.zoom_factor).to_pos2());
+            if self.board_cache.as_ref().is_none_or(|(cached, area, _)| *cached != key || !area.contains_rect(visible)) {
+                let area = visible.expand2(visible.size() / 2.0);
+                self.board_cache = Some((key, area, self.board_shapes(&painter, &geometry, area)));
+            }
+            if let Some((_, _, shapes)) = &self.board_cache {
+                let offset = self.view_origin.to_vec2();
+                painter.extend(shapes.iter().cloned().map(|mut shape| {shape.translate(offset); shape}));
             }
-            
-            // Paint lines
-            for iw in 0..c_ww {
-            for iu in 0..c_uu {
-            for iy in 0..c_yy {
-                let spc_y = self.cum_spc_y(iy,iu,iw);
-                let pos_y = (iy+iu*c_yy+iw*c_yy*c_uu) as f32 * self.cell_edge + spc_y;
-                for iv in 0..c_vv {
-                for iz in 0..c_zz {
-                for ix in 0..c_xx {
-                for ix2 in 0..=1 {
-                    let spc_x = self.cum_spc_x(ix,iz,iv);
-                    let ulc = self.sc_tr((ix+iz*c_xx+iv*c_xx*c_zz+ix2) as f32 * self.cell_edge + spc_x, pos_y);
-                    if ulc.x >= -self.cell_edge*self.zoom_factor && ulc.x <= screen_size.x
-                       && ulc.y >= -self.cell_edge*self.zoom_factor && ulc.y <= screen_size.y+self.cell_edge {
-                        painter.add(
-                            Shape::line_segment(
-                                [ulc,
-                                self.sc_tr((ix+iz*c_xx+iv*c_xx*c_zz+ix2) as f32 * self.cell_edge + spc_x,
-                                            pos_y + self.cell_edge)],
-                                if (ix == 0 && ix2 == 0)
-                                    || (ix+1 == c_xx && ix2 == 1)
-                                {harder_stroke} else {basic_stroke}));
-                    }
-                }}}}
-            }}}
-            for iv in 0..c_vv {
-            for iz in 0..c_zz {
-            for ix in 0..c_xx {
-                let spc_x = self.cum_spc_x(ix,iz,iv);
-                let pos_x = (ix+iz*c_xx+iv*c_xx*c_zz) as f32 * self.cell_edge + spc_x;
-                for iw in 0..c_ww {
-                for iu in 0..c_uu {
-                for iy in 0..c_yy {
-                for iy2 in 0..=1 {
-                    let spc_y = self.cum_spc_y(iy,iu,iw);
-                    let ulc = self.sc_tr(pos_x, (iy+iu*c_yy+iw*c_yy*c_uu+iy2) as f32*self.cell_edge + spc_y);
-                    if ulc.x >= -self.cell_edge*self.zoom_factor && ulc.x <= screen_size.x
-                       && ulc.y >= -self.cell_edge*self.zoom_factor && ulc.y <= screen_size.y {
-                        painter.add(
-                            Shape::line_segment(
-                                [ulc,
-                                 self.sc_tr(pos_x + self.cell_edge,
-                                            (iy+iu*c_yy+iw*c_yy*c_uu+iy2) as f32*self.cell_edge + spc_y)],
-                                if (iy == 0 && iy2 == 0)
-                                   || (iy+1 == c_yy && iy2 == 1)
-                                {harder_stroke} else {basic_stroke}));
-                    }
-                }}}}
-            }}}
             
             // Paint cursor, neighbor hints and their center
             if let Some(pos) = painter_response.hover_pos() {
@@ -977,168 +2438,107 @@ If neighbor hints are enabled, holding Shift freezes them in place, whereas hold
                 
                 if let Some([ix, iy, iz, iu, iv, iw]) = neighbor_coords {
                     let [cw_xx, cw_yy, cw_zz, cw_uu, cw_vv, cw_ww] = self.current_initial_settings.wrap;
+                    let mut hidden_neighbors = 0;
                     for iwsupp in BWI::new(iw as i32-1,iw as i32+1,0,c_ww as i32-1,cw_ww) {
                     for ivsupp in BWI::new(iv as i32-1,iv as i32+1,0,c_vv as i32-1,cw_vv) {
                     for iusupp in BWI::new(iu as i32-1,iu as i32+1,0,c_uu as i32-1,cw_uu) {
                     for izsupp in BWI::new(iz as i32-1,iz as i32+1,0,c_zz as i32-1,cw_zz) {
                     for iysupp in BWI::new(iy as i32-1,iy as i32+1,0,c_yy as i32-1,cw_yy) {
                     for ixsupp in BWI::new(ix as i32-1,ix as i32+1,0,c_xx as i32-1,cw_xx) {
-                        let (ixb, iyb, izb, iub, ivb, iwb)
-                            = (ixsupp as usize, iysupp as usize, izsupp as usize,
-                               iusupp as usize, ivsupp as usize, iwsupp as usize);
-                        let (spc_x, spc_y) = (self.cum_spc_x(ixb,izb,ivb), self.cum_spc_y(iyb,iub,iwb));
-                        let (ulc_x, ulc_y) = (ixb+izb*c_xx+ivb*c_xx*c_zz, iyb+iub*c_yy+iwb*c_yy*c_uu);
-                        painter.add(
-                            Shape::rect_stroke(
-                                Rect::from_min_max(
-                                    self.sc_tr(ulc_x as f32 * self.cell_edge + spc_x,
-                                               ulc_y as f32 * self.cell_edge + spc_y),
-                                    self.sc_tr((ulc_x+1) as f32 * self.cell_edge + spc_x,
-                                               (ulc_y+1) as f32 * self.cell_edge + spc_y)),
-                                    Rounding::ZERO, neighbor_stroke));
+                        let neighbor = [ixsupp, iysupp, izsupp, iusupp, ivsupp, iwsupp].map(|c| c as usize);
+                        let mut shown = false;
+                        for rect in self.cell_rects(&geometry, neighbor) {
+                            painter.add(Shape::rect_stroke(rect, Rounding::ZERO, neighbor_stroke));
+                            shown = true;
+                        }
+                        hidden_neighbors += usize::from(!shown);
                     }}}}}}
                     
-                    let (spc_x, spc_y) = (self.cum_spc_x(ix,iz,iv), self.cum_spc_y(iy,iu,iw));
-                    let (ulc_x, ulc_y) = (ix+iz*c_xx+iv*c_xx*c_zz, iy+iu*c_yy+iw*c_yy*c_uu);
-                    painter.add(
-                        Shape::rect_stroke(
-                            Rect::from_min_max(
-                                self.sc_tr(ulc_x as f32 * self.cell_edge + spc_x,
-                                           ulc_y as f32 * self.cell_edge + spc_y),
-                                self.sc_tr((ulc_x+1) as f32 * self.cell_edge + spc_x,
-                                           (ulc_y+1) as f32 * self.cell_edge + spc_y)),
-                            Rounding::ZERO, center_stroke));
+                    for rect in self.cell_rects(&geometry, [ix, iy, iz, iu, iv, iw]) {
+                        painter.add(Shape::rect_stroke(rect, Rounding::ZERO, center_stroke));
+                        // Neighbors in slices that aren't shown are only counted
+                        if hidden_neighbors > 0 {
+                            painter.text(rect.right_top(), Align2::LEFT_BOTTOM, format!("+{}", hidden_neighbors),
+                                         FontId::proportional(14.0 * self.zoom_factor), self.neighbor_color);
+                        }
+                    }
                 }
-                if let Some([ix, iy, iz, iu, iv, iw]) = mouse_coords {
-                    let (spc_x, spc_y) = (self.cum_spc_x(ix,iz,iv), self.cum_spc_y(iy,iu,iw));
-                    let (ulc_x, ulc_y) = (ix+iz*c_xx+iv*c_xx*c_zz, iy+iu*c_yy+iw*c_yy*c_uu);
-                    painter.add(
-                        Shape::rect_stroke(
-                            Rect::from_min_max(
-                                self.sc_tr(ulc_x as f32 * self.cell_edge + spc_x,
-                                           ulc_y as f32 * self.cell_edge + spc_y),
-                                self.sc_tr((ulc_x+1) as f32 * self.cell_edge + spc_x,
-                                           (ulc_y+1) as f32 * self.cell_edge + spc_y)),
-                            Rounding::ZERO, selection_stroke));
+                if let Some(coords) = mouse_coords {
+                    for rect in self.cell_rects(&geometry, coords) {
+                        painter.add(Shape::rect_stroke(rect, Rounding::ZERO, selection_stroke));
+                    }
                 }
             }
-            
-            // Paint highlights
-            const HIGHLIGHT_SPACING: f32 = 2.5;
-            for iw in 0..c_ww {
-            for iv in 0..c_vv {
-            for iu in 0..c_uu {
-            for iz in 0..c_zz {
-            for iy in 0..c_yy {
-            for ix in 0..c_xx {
-                if let Some(game) = &self.game {
-                    match game.cell_at([ix, iy, iz, iu, iv, iw]) {
-                        CellState::UndiscoveredMine(g) | CellState::MarkedMine(g)
-                        | CellState::ExplodedMine(g) | CellState::UndiscoveredEmpty(.., g)
-                        | CellState::MarkedEmpty(.., g) | CellState::DiscoveredEmpty(.., g)
-                            => {
-                            let (spc_x, spc_y) = (self.cum_spc_x(ix,iz,iv), self.cum_spc_y(iy,iu,iw));
-                            let mut next_start_group = 0;
-                            if g > 0 { for current_side in 0..8 {
-                                for highlight_group in (next_start_group..8).chain(0..next_start_group) {
-                                    if (g & (1 << highlight_group)) > 0 {
-                                        let mut p1x = (ix+iz*c_xx+iv*c_xx*c_zz) as f32 * self.cell_edge + spc_x;
-                                        let mut p1y = (iy+iu*c_yy+iw*c_yy*c_uu) as f32 * self.cell_edge + spc_y;
-                                        let mut p2x = (ix+iz*c_xx+iv*c_xx*c_zz) as f32 * self.cell_edge + spc_x;
-                                        let mut p2y = (iy+iu*c_yy+iw*c_yy*c_uu) as f32 * self.cell_edge + spc_y;
-                                        match current_side {
-                                            0 | 6 | 7 => {p1x += HIGHLIGHT_SPACING;},
-                                            1 | 5 => {p1x += self.cell_edge/2.0;},
-                                            2 | 3 | 4 => {p1x += self.cell_edge-HIGHLIGHT_SPACING;},
-                                            _ => {}
-                                        };
-                                        match current_side {
-                                            0 | 1 | 2 => {p1y += HIGHLIGHT_SPACING;},
-                                            3 | 7 => {p1y += self.cell_edge/2.0;},
-                                            4 | 5 | 6 => {p1y += self.cell_edge-HIGHLIGHT_SPACING;},
-                                            _ => {}
-                                        };
-                                        match current_side {
-                                            0 | 4 => {p2x += self.cell_edge/2.0;},
-                                            1 | 2 | 3 => {p2x += self.cell_edge-HIGHLIGHT_SPACING;},
-                                            5 | 6 | 7 => {p2x += HIGHLIGHT_SPACING;},
-                                            _ => {}
-                                        };
-                                        match current_side {
-                                            0 | 1 | 7 => {p2y += HIGHLIGHT_SPACING;},
-                                            2 | 6 => {p2y += self.cell_edge/2.0;},
-                                            3 | 4 | 5 => {p2y += self.cell_edge-HIGHLIGHT_SPACING;},
-                                            _ => {}
-                                        };
-                                        
-                                        painter.add(
-                                            Shape::line_segment([self.sc_tr(p1x, p1y),
-                                                                 self.sc_tr(p2x, p2y)],
-                                                                highlight_strokes[highlight_group]));
-                                        next_start_group = (highlight_group + 1) % 8;
-                                        break;
-                                    }
-                                }
-                            }}
-                        }
-                    };
+            if let Some(coords) = self.keyboard_cursor() {
+                for rect in self.cell_rects(&geometry, coords) {
+                    painter.add(Shape::rect_stroke(rect, Rounding::ZERO, selection_stroke));
                 }
-            }}}}}}
+            }
             
-            // React to clicks
-            // TODO: Maybe polymorphism/enum impl wouldn't be a bad idea here
-            if painter_response.clicked_by(PointerButton::Primary) {
-                // println!("primary click");
-                if CursorMode::ProbeAndMark == self.cursor_mode {
-                    if let Some(pos) = ctx.pointer_interact_pos() {
-                        if let Some(coords) = self.get_coords(pos) {
-                            if let Some(game) = &mut self.game {
-                                if game.state() != GameState::Victory && game.state() != GameState::Loss {
-                                    match game.probe_at(coords, self.probe_marked) {
-                                        GameState::Victory | GameState::Loss => {
-                                            self.end_time = Some(SystemTime::now());
-                                        },
-                                        GameState::Running => {}
-                                    }
-                                }
-                            } else {
-                                self.start(coords);
-                            }
-                        }
+            // Paint cells involved in the loss
+            if let (true, Some(post_mortem)) = (self.post_mortem_window_enabled, &self.post_mortem) {
+                let wrong_flag_stroke = Stroke::new(4.0 * self.zoom_factor, self.wrong_flag_color);
+                let missed_mine_stroke = Stroke::new(4.0 * self.zoom_factor, self.missed_mine_color);
+                for coords in &post_mortem.wrong_flags {
+                    for rect in self.cell_rects(&geometry, *coords) {
+                        painter.add(Shape::rect_stroke(rect, Rounding::ZERO, wrong_flag_stroke));
                     }
-                } else if self.cursor_mode == CursorMode::Highlighter {
-                    if let Some(pos) = ctx.pointer_interact_pos() {
-                        if let Some(coords) = self.get_coords(pos) {
-                            if let Some(game) = &mut self.game {
-                                game.highlight_at(coords, self.selected_highlighters, true);
-                            }
-                        }
+                }
+                for coords in &post_mortem.missed_mines {
+                    for rect in self.cell_rects(&geometry, *coords) {
+                        painter.add(Shape::rect_stroke(rect, Rounding::ZERO, missed_mine_stroke));
+                    }
+                }
+                if let Some(coords) = post_mortem.fatal_cell {
+                    for rect in self.cell_rects(&geometry, coords) {
+                        painter.add(Shape::rect_stroke(rect, Rounding::ZERO,
+                                                       Stroke::new(5.0 * self.zoom_factor, self.selection_color)));
                     }
                 }
             }
-            if painter_response.clicked_by(PointerButton::Secondary) {
-                // println!("secondary click");
-                if CursorMode::ProbeAndMark == self.cursor_mode {
-                    if let Some(pos) = ctx.pointer_interact_pos() {
-                        if let Some(coords) = self.get_coords(pos) {
-                            if let Some(game) = &mut self.game {
-                                if game.state() != GameState::Victory && game.state() != GameState::Loss {
-                                    game.mark_at(coords);
-                                }
-                            }
-                        }
+            
+            // Touch: holding a finger in place acts as the secondary button
+            let long_press_coords = if self.long_press(ctx) {
+                ctx.input(|i| i.pointer.press_origin()).and_then(|pos| self.get_coords(pos))
+            } else {
+                None
+            };
+            let clicked_coords = |button| if painter_response.clicked_by(button) {
+                ctx.pointer_interact_pos().and_then(|pos| self.get_coords(pos))
+            } else {
+                None
+            };
+            // Tap that ended a long press (or a gesture) is not a click
+            let mut primary = clicked_coords(PointerButton::Primary).filter(|_| !self.touch_press_handled);
+            let mut secondary = clicked_coords(PointerButton::Secondary).or(long_press_coords);
+            if self.flag_mode && self.cursor_mode == CursorMode::ProbeAndMark {
+                std::mem::swap(&mut primary, &mut secondary);
+            }
+            
+            // React to clicks (the board is read-only while a replay is playing)
+            if self.replay_playback.is_none() {
+                if let Some(coords) = primary {
+                    match self.cursor_mode {
+                        CursorMode::ProbeAndMark => self.probe_action(coords),
+                        CursorMode::Highlighter => if let Some(game) = &mut self.game {
+                            game.highlight_at(coords, self.selected_highlighters, true);
+                        },
                     }
-                } else if self.cursor_mode == CursorMode::Highlighter {
-                    if let Some(pos) = ctx.pointer_interact_pos() {
-                        if let Some(coords) = self.get_coords(pos) {
-                            if let Some(game) = &mut self.game {
-                                game.highlight_at(coords, self.selected_highlighters, false);
-                            }
-                        }
+                }
+                if let Some(coords) = secondary {
+                    match self.cursor_mode {
+                        CursorMode::ProbeAndMark => self.mark_action(coords),
+                        CursorMode::Highlighter => if let Some(game) = &mut self.game {
+                            game.highlight_at(coords, self.selected_highlighters, false);
+                        },
                     }
                 }
             }
-            if painter_response.dragged() {
+            let multi_touch = ctx.multi_touch();
+            if let Some(touch) = multi_touch {
+                // Two-finger pan
+                self.view_origin += touch.translation_delta;
+            } else if painter_response.dragged() {
                 if ui.input(|i| i.pointer.button_down(PointerButton::Middle)) {
                     //println!("dragged");
                     self.view_origin += painter_response.drag_delta();
@@ -1164,13 +2564,19 @@ If neighbor hints are enabled, holding Shift freezes them in place, whereas hold
                     }
                 }
             }
-            // TODO: accept zoom_delta()
-            // Zoom/unzoom
-            if painter_response.hovered() {
+            // Zoom/unzoom, the scroll wheel zooms in steps, pinching (or Ctrl+scroll) smoothly
+            if painter_response.hovered() || multi_touch.is_some() {
                 let scroll_delta = ctx.input(|i| i.raw_scroll_delta);
+                let zoom_delta = ctx.input(|i| i.zoom_delta());
                 //println!("{:?}", scroll_delta.y);
                 
-                let factor = if scroll_delta.y > 0.0 && (self.zoom_factor < 5.0 || self.unlimited_zoom) { 1.5 } else if scroll_delta.y < 0.0 && (self.zoom_factor > 0.01 || self.unlimited_zoom) { 0.66 } else { 0.0 };
+                let factor = if zoom_delta != 1.0 {
+                    if self.unlimited_zoom {
+                        zoom_delta
+                    } else {
+                        (self.zoom_factor * zoom_delta).clamp(0.01, 5.0) / self.zoom_factor
+                    }
+                } else if scroll_delta.y > 0.0 && (self.zoom_factor < 5.0 || self.unlimited_zoom) { 1.5 } else if scroll_delta.y < 0.0 && (self.zoom_factor > 0.01 || self.unlimited_zoom) { 0.66 } else { 0.0 };
                 
                 if factor != 0.0 {
                     if let Some(pos) = ctx.pointer_interact_pos() {
@@ -1182,27 +2588,67 @@ If neighbor hints are enabled, holding Shift freezes them in place, whereas hold
                 }
             }
             // Keyboard Shortcuts
-            //   The check below is to prevent triggering when trying to type
-            //     the seed in the new game window. It's a bit crude, but it works.
-            if !self.new_game_window_enabled {
-                if ui.input_mut(|i| i.consume_shortcut(&self.shortcuts.probe_mark_shortcut)) {
-                    self.try_set_cursor(CursorMode::ProbeAndMark);
-                }
-                if ui.input_mut(|i| i.consume_shortcut(&self.shortcuts.highlighter_shortcut)) {
-                    self.try_set_cursor(CursorMode::Highlighter);
-                }
-                for ii in 0..8 {
-                    if ui.input_mut(|i| i.consume_shortcut(&self.shortcuts.highlight_group_shortcuts[ii])) {
-                        self.selected_highlighters ^= 1 << ii;
+            //   Not while typing into a text field (seed, preset names, configuration)
+            if !ctx.wants_keyboard_input() {
+                let cursor = self.keyboard_cursor();
+                // Actions at the cursor follow the same rules as clicks (nothing while a replay is playing)
+                let playing = self.replay_playback.is_some();
+                for action in self.shortcuts.consume_pressed(ctx) {
+                    match action {
+                        Shortcuts::PROBE_MARK => self.try_set_cursor(CursorMode::ProbeAndMark),
+                        Shortcuts::HIGHLIGHTER => self.try_set_cursor(CursorMode::Highlighter),
+                        Shortcuts::RESET_VIEW => self.reset_view(),
+                        Shortcuts::ZOOM_TO_FIT => self.zoom_to_fit(ctx.screen_rect().max),
+                        Shortcuts::TRANSPOSE_LAYOUT => self.set_layout(self.layout.transposed(), ctx.screen_rect().max),
+                        Shortcuts::AUTO_LAYOUT => {
+                            self.auto_layout = !self.auto_layout;
+                            self.preferences_dirty = true;
+                        },
+                        Shortcuts::SLICE_VIEW => self.toggle_slice_view(ctx.screen_rect().max),
+                        Shortcuts::MINIMAP => {
+                            self.show_minimap = !self.show_minimap;
+                            self.preferences_dirty = true;
+                        },
+                        Shortcuts::GO_TO => self.go_to_window_enabled = !self.go_to_window_enabled,
+                        Shortcuts::FIND_OPEN_NUMBER => self.find_next(CellQuery::OpenNumber),
+                        Shortcuts::FIND_SAFE_CELL => self.find_next(CellQuery::SafeCell),
+                        Shortcuts::HIGHLIGHT_NEIGHBORS => {
+                            let hovered = ctx.pointer_hover_pos().and_then(|pos| self.get_coords(pos));
+                            if let (Some(coords), Some(game), false) = (hovered.or(cursor), &mut self.game, playing) {
+                                game.highlight_undiscovered_neighbors(coords, self.highlight_operands[2] - 1);
+                            }
+                        },
+                        Shortcuts::CURSOR_PROBE => if let (Some(coords), false) = (cursor, playing) {
+                            self.probe_action(coords);
+                        },
+                        Shortcuts::CURSOR_MARK => if let (Some(coords), false) = (cursor, playing) {
+                            self.mark_action(coords);
+                        },
+                        Shortcuts::CURSOR_CHORD => if let (Some(coords), false) = (cursor, playing) {
+                            self.chord_action(coords);
+                        },
+                        Shortcuts::CURSOR_HIGHLIGHT => if let (Some(coords), Some(game), false)
+                                                             = (cursor, &mut self.game, playing) {
+                            let groups = self.selected_highlighters;
+                            let highlighted = game.cell_at(coords).highlight_groups() & groups == groups;
+                            game.highlight_at(coords, groups, !highlighted);
+                        },
+                        movement if movement >= Shortcuts::CURSOR_MOVE => {
+                            let hovered = ctx.pointer_hover_pos().and_then(|pos| self.get_coords(pos));
+                            let start = hovered.or(self.slice_view.then(|| self.slice.center(self.current_initial_settings.size)));
+                            let axis = (movement - Shortcuts::CURSOR_MOVE) / 2;
+                            self.move_keyboard_cursor(axis, (movement - Shortcuts::CURSOR_MOVE) % 2 == 1, start);
+                            if let Some(coords) = self.keyboard_cursor() {
+                                // Moving along a fixed axis goes to the next slice
+                                if self.slice_view {
+                                    self.slice.fixed = coords;
+                                }
+                                self.scroll_to_cell(coords, painter_response.rect);
+                            }
+                        },
+                        group => self.selected_highlighters ^= 1 << (group - Shortcuts::HIGHLIGHT_GROUP_1),
                     }
                 }
-                
-                if ui.input_mut(|i| i.consume_shortcut(&self.shortcuts.reset_view_shortcut)) {
-                    self.reset_view();
-                }
-                if ui.input_mut(|i| i.consume_shortcut(&self.shortcuts.zoom_to_fit_shortcut)) {
-                    self.zoom_to_fit(ctx.screen_rect().max);
-                }
             }
         });
     }
@@ -3,11 +3,16 @@ extern crate rand;
 use crate::bwi::BWI;
 use rand_chacha::{ChaCha8Rng, rand_core::SeedableRng};
 use self::rand::Rng;
+use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
 
 pub const DIMENSIONS_COUNT: usize = 6;
 
-#[derive(Clone,PartialEq)]
+// Source of `GameBoard::revision`, shared by all boards so that two boards never have the same one
+static NEXT_REVISION: AtomicU64 = AtomicU64::new(1);
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct InitialGameSettings {
     pub name: String,
     pub size: [usize; DIMENSIONS_COUNT],
@@ -16,6 +21,14 @@ pub struct InitialGameSettings {
     pub seed: Option<String>,
 }
 
+impl InitialGameSettings {
+    // Human readable dimensions, e.g. "4 x 4 x 3 x 4w x 1 x 1"
+    pub fn size_description(&self) -> String {
+        (0..DIMENSIONS_COUNT).map(|i| format!("{}{}", self.size[i], if self.wrap[i] {"w"} else {""}))
+                             .collect::<Vec<_>>().join(" x ")
+    }
+}
+
 impl Default for InitialGameSettings {
     fn default() -> Self {
         Self {
@@ -28,14 +41,14 @@ impl Default for InitialGameSettings {
     }
 }
 
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum GameState {
     Running,
     Victory,
     Loss,
 }
 
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum CellState {
     // Mine is either undiscoved, marked or exploded
     // u8 are highlight groups
@@ -51,7 +64,68 @@ pub enum CellState {
     DiscoveredEmpty(u32, i32, u8), // u32 || i32 (when delta is enabled)
 }
 
-#[derive(Debug, PartialEq)]
+impl CellState {
+    pub fn highlight_groups(&self) -> u8 {
+        match *self {
+            CellState::UndiscoveredMine(g) | CellState::MarkedMine(g) | CellState::ExplodedMine(g)
+            | CellState::UndiscoveredEmpty(.., g) | CellState::MarkedEmpty(.., g) | CellState::DiscoveredEmpty(.., g) => g,
+        }
+    }
+
+    // True number of neighboring mines of an empty cell, whatever the player sees of it
+    fn empty_value(self) -> Option<u32> {
+        match self {
+            CellState::UndiscoveredEmpty(c, ..) | CellState::MarkedEmpty(c, ..) | CellState::DiscoveredEmpty(c, ..) => Some(c),
+            _ => None
+        }
+    }
+
+    fn with_highlight_groups(self, g: u8) -> CellState {
+        match self {
+            CellState::UndiscoveredMine(_) => CellState::UndiscoveredMine(g),
+            CellState::MarkedMine(_) => CellState::MarkedMine(g),
+            CellState::ExplodedMine(_) => CellState::ExplodedMine(g),
+            CellState::UndiscoveredEmpty(c, d, _) => CellState::UndiscoveredEmpty(c, d, g),
+            CellState::MarkedEmpty(c, d, _) => CellState::MarkedEmpty(c, d, g),
+            CellState::DiscoveredEmpty(c, d, _) => CellState::DiscoveredEmpty(c, d, g),
+        }
+    }
+}
+
+// Set operations on whole highlight groups
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum HighlightOperation {
+    Union,
+    Intersection,
+    Difference, // cells of the first group that aren't in the second one
+}
+
+// Cells counted by what the player can see of them
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct NeighborCounts {
+    pub undiscovered: u32,
+    pub flagged: u32,
+    pub revealed: u32, // exploded mines included
+}
+
+impl NeighborCounts {
+    fn add(&mut self, state: CellState) {
+        match state {
+            CellState::UndiscoveredMine(_) | CellState::UndiscoveredEmpty(..) => self.undiscovered += 1,
+            CellState::MarkedMine(_) | CellState::MarkedEmpty(..) => self.flagged += 1,
+            CellState::ExplodedMine(_) | CellState::DiscoveredEmpty(..) => self.revealed += 1,
+        }
+    }
+}
+
+// Kinds of cells "find next" looks for, where progress can still be made
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CellQuery {
+    OpenNumber, // revealed number with mines left to flag and undiscovered neighbors
+    SafeCell,   // undiscovered, unflagged cell next to a number whose mines are all flagged
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct GameBoard {
     // x, y, z, u, v, w
     size: [usize; DIMENSIONS_COUNT],
@@ -67,6 +141,32 @@ pub struct GameBoard {
     marked_as_mine: u64,
     undiscoved_empty_fields: u64,
     total_fields: u64,
+    bbbv: u32,
+    
+    // Changes whenever a cell might have changed, front-ends compare it to tell if they have to redraw.
+    // A loaded board gets a fresh one, so it never matches a board drawn before.
+    #[serde(skip, default = "next_revision")]
+    revision: u64,
+}
+
+fn next_revision() -> u64 {
+    NEXT_REVISION.fetch_add(1, Ordering::Relaxed)
+}
+
+// Boards are equal when their contents are, regardless of when they last changed
+impl PartialEq for GameBoard {
+    fn eq(&self, other: &Self) -> bool {
+        self.size == other.size
+            && self.wrap == other.wrap
+            && self.seed == other.seed
+            && self.board == other.board
+            && self.state == other.state
+            && self.mine_count == other.mine_count
+            && self.marked_as_mine == other.marked_as_mine
+            && self.undiscoved_empty_fields == other.undiscoved_empty_fields
+            && self.total_fields == other.total_fields
+            && self.bbbv == other.bbbv
+    }
 }
 
 impl GameBoard {
@@ -79,14 +179,146 @@ impl GameBoard {
     pub fn marked_as_mine(&self) -> u64 {self.marked_as_mine}
     pub fn undiscoved_empty_fields(&self) -> u64 {self.undiscoved_empty_fields}
     pub fn total_fields(&self) -> u64 {self.total_fields}
+    pub fn bbbv(&self) -> u32 {self.bbbv}
+    pub fn revision(&self) -> u64 {self.revision}
+    
+    fn touch(&mut self) {
+        self.revision = next_revision();
+    }
     
     pub fn cell_at(&self, coordinates: [usize; DIMENSIONS_COUNT]) -> CellState {
         let [xx, yy, zz, uu, vv, ww] = coordinates;
         self.board[ww][vv][uu][zz][yy][xx]
     }
+
+    // Neighbors of given cell, in the same order and multiplicity as they are counted
+    // (on wrapping dimensions of size 1 or 2 the same cell may be reached more than once)
+    pub fn neighbors(&self, coordinates: [usize; DIMENSIONS_COUNT]) -> Vec<[usize; DIMENSIONS_COUNT]> {
+        let [xx, yy, zz, uu, vv, ww] = coordinates;
+        let [s_x, s_y, s_z, s_u, s_v, s_w] = self.size;
+        let [w_x, w_y, w_z, w_u, w_v, w_w] = self.wrap;
+
+        let mut ret = Vec::new();
+        for iwsupp in BWI::new(ww as i32-1,ww as i32+1,0,s_w as i32-1,w_w) {
+        for ivsupp in BWI::new(vv as i32-1,vv as i32+1,0,s_v as i32-1,w_v) {
+        for iusupp in BWI::new(uu as i32-1,uu as i32+1,0,s_u as i32-1,w_u) {
+        for izsupp in BWI::new(zz as i32-1,zz as i32+1,0,s_z as i32-1,w_z) {
+        for iysupp in BWI::new(yy as i32-1,yy as i32+1,0,s_y as i32-1,w_y) {
+        for ixsupp in BWI::new(xx as i32-1,xx as i32+1,0,s_x as i32-1,w_x) {
+            if iwsupp != ww as i32 || ivsupp != vv as i32 || iusupp != uu as i32
+               || izsupp != zz as i32 || iysupp != yy as i32 || ixsupp != xx as i32 {
+                ret.push([ixsupp as usize, iysupp as usize, izsupp as usize,
+                          iusupp as usize, ivsupp as usize, iwsupp as usize]);
+            }
+        }}}}}}
+        ret
+    }
+
+    // Highlight groups below are numbered from 0, unlike the bitmask of `highlight_at`
+    
+    // Sets the membership of every cell in given group from the groups it is in
+    fn update_highlight_group(&mut self, group: usize, member: impl Fn(u8) -> bool) {
+        self.touch();
+        for cell in self.board.iter_mut().flatten().flatten().flatten().flatten().flatten() {
+            let g = cell.highlight_groups();
+            *cell = cell.with_highlight_groups(if member(g) {g | 1 << group} else {g & !(1 << group)});
+        }
+    }
+
+    pub fn clear_highlight_group(&mut self, group: usize) {
+        self.update_highlight_group(group, |_| false);
+    }
+
+    // Replaces the target group with the result of the operation, which may use it as an operand too
+    pub fn combine_highlight_groups(&mut self, operation: HighlightOperation, first: usize, second: usize,
+                                    target: usize) {
+        self.update_highlight_group(target, |g| {
+            let (a, b) = (g & 1 << first != 0, g & 1 << second != 0);
+            match operation {
+                HighlightOperation::Union => a || b,
+                HighlightOperation::Intersection => a && b,
+                HighlightOperation::Difference => a && !b,
+            }
+        });
+    }
+
+    // Replaces given group with the undiscovered (and unflagged) neighbors of given cell
+    pub fn highlight_undiscovered_neighbors(&mut self, coordinates: [usize; DIMENSIONS_COUNT], group: usize) {
+        self.clear_highlight_group(group);
+        for neighbor in self.neighbors(coordinates) {
+            if let CellState::UndiscoveredMine(_) | CellState::UndiscoveredEmpty(..) = self.cell_at(neighbor) {
+                self.highlight_at(neighbor, 1 << group, true);
+            }
+        }
+    }
+
+    // Neighbors of given cell by state, all of them and the ones differing from the cell along each axis
+    pub fn neighbor_counts(&self, coordinates: [usize; DIMENSIONS_COUNT])
+            -> (NeighborCounts, [NeighborCounts; DIMENSIONS_COUNT]) {
+        let mut total = NeighborCounts::default();
+        let mut by_axis = [NeighborCounts::default(); DIMENSIONS_COUNT];
+        for neighbor in self.neighbors(coordinates) {
+            let state = self.cell_at(neighbor);
+            total.add(state);
+            for axis in (0..DIMENSIONS_COUNT).filter(|&a| neighbor[a] != coordinates[a]) {
+                by_axis[axis].add(state);
+            }
+        }
+        (total, by_axis)
+    }
+
+    pub fn matches(&self, coordinates: [usize; DIMENSIONS_COUNT], query: CellQuery) -> bool {
+        let undiscovered = |c| matches!(self.cell_at(c), CellState::UndiscoveredMine(_) | CellState::UndiscoveredEmpty(..));
+        match query {
+            CellQuery::OpenNumber => matches!(self.cell_at(coordinates), CellState::DiscoveredEmpty(_, d, _) if d > 0)
+                                     && self.neighbors(coordinates).into_iter().any(undiscovered),
+            CellQuery::SafeCell => undiscovered(coordinates)
+                                   && self.neighbors(coordinates).into_iter()
+                                          .any(|n| matches!(self.cell_at(n), CellState::DiscoveredEmpty(_, 0, _))),
+        }
+    }
+
+    // First cell matching the query after given one in the order of `all_coords`, wrapping around
+    pub fn find_next(&self, query: CellQuery, after: Option<[usize; DIMENSIONS_COUNT]>)
+            -> Option<[usize; DIMENSIONS_COUNT]> {
+        let start = after.map_or(0, |c| self.linear_index(c) + 1);
+        self.all_coords().skip(start).chain(self.all_coords().take(start)).find(|&c| self.matches(c, query))
+    }
+
+    // All coordinates of the board, x changing fastest
+    pub fn all_coords(&self) -> impl Iterator<Item = [usize; DIMENSIONS_COUNT]> {
+        let [s_x, s_y, s_z, s_u, s_v, s_w] = self.size;
+        (0..s_x*s_y*s_z*s_u*s_v*s_w).map(move |ii| {
+            [ii % s_x, ii / s_x % s_y, ii / (s_x*s_y) % s_z, ii / (s_x*s_y*s_z) % s_u,
+             ii / (s_x*s_y*s_z*s_u) % s_v, ii / (s_x*s_y*s_z*s_u*s_v)]
+        })
+    }
+
+    // Position of given cell in the order produced by `all_coords`
+    pub fn linear_index(&self, coordinates: [usize; DIMENSIONS_COUNT]) -> usize {
+        let [xx, yy, zz, uu, vv, ww] = coordinates;
+        let [s_x, s_y, s_z, s_u, s_v, _] = self.size;
+        ((((ww*s_v + vv)*s_u + uu)*s_z + zz)*s_y + yy)*s_x + xx
+    }
+
+    // Whether `probe_at` with the same arguments would hit a mine
+    pub fn probe_explodes(&self, coordinates: [usize; DIMENSIONS_COUNT], probe_marked: bool) -> bool {
+        match self.cell_at(coordinates) {
+            CellState::UndiscoveredMine(_) => true,
+            CellState::MarkedMine(_) => probe_marked,
+            _ => false
+        }
+    }
+    
+    // Whether `chord_at` would hit a mine, i.e. some flag around the number is misplaced
+    pub fn chord_explodes(&self, coordinates: [usize; DIMENSIONS_COUNT]) -> bool {
+        matches!(self.cell_at(coordinates), CellState::DiscoveredEmpty(_, 0, _))
+            && self.neighbors(coordinates).into_iter().any(|n| matches!(self.cell_at(n), CellState::UndiscoveredMine(_)))
+    }
     
     // Used for discovering undiscovered fields
     pub fn probe_at(&mut self, coordinates: [usize; DIMENSIONS_COUNT], probe_marked: bool) -> GameState {
+        self.touch();
         let [xx, yy, zz, uu, vv, ww] = coordinates;
         let [s_x, s_y, s_z, s_u, s_v, s_w] = self.size;
         let [w_x, w_y, w_z, w_u, w_v, w_w] = self.wrap;
@@ -146,8 +378,28 @@ impl GameBoard {
         return self.state;
     }
     
+    // Used for probing all undiscovered neighbors of a discovered cell,
+    //   only does something if all neighboring mines are already marked (delta is zero)
+    pub fn chord_at(&mut self, coordinates: [usize; DIMENSIONS_COUNT]) -> GameState {
+        if let CellState::DiscoveredEmpty(_, 0, _) = self.cell_at(coordinates) {
+            for neighbor in self.neighbors(coordinates) {
+                if self.state == GameState::Loss {
+                    break;
+                }
+                match self.cell_at(neighbor) {
+                    CellState::UndiscoveredMine(_) | CellState::UndiscoveredEmpty(..) => {
+                        self.probe_at(neighbor, false);
+                    },
+                    _ => {}
+                }
+            }
+        }
+        self.state
+    }
+    
     // Used for marking/unmarking cells as mines
     pub fn mark_at(&mut self, coordinates: [usize; DIMENSIONS_COUNT]) {
+        self.touch();
         let [xx, yy, zz, uu, vv, ww] = coordinates;
         let [s_x, s_y, s_z, s_u, s_v, s_w] = self.size;
         let [w_x, w_y, w_z, w_u, w_v, w_w] = self.wrap;
@@ -245,6 +497,7 @@ impl GameBoard {
     
     // Highlight given cell (enable = highlight, !enable = unhighlight)
     pub fn highlight_at(&mut self, coordinates: [usize; DIMENSIONS_COUNT], group: u8, enable: bool) {
+        self.touch();
         let [xx, yy, zz, uu, vv, ww] = coordinates;
         if enable {
             self.board[ww][vv][uu][zz][yy][xx] = match self.board[ww][vv][uu][zz][yy][xx] {
@@ -267,6 +520,58 @@ impl GameBoard {
         }
     }
     
+    // Minimal number of clicks needed to clear the board (Bechtel's Board Benchmark Value):
+    //   every opening (connected area of zeros including its border) counts once,
+    //   every other empty cell not bordering an opening counts once as well
+    // Part of the 3BV the player has already done: fully discovered openings
+    // and discovered numbers outside of openings. Equals `bbbv` once the board is cleared.
+    pub fn bbbv_cleared(&self) -> u32 {
+        self.count_bbbv(true)
+    }
+    
+    // Openings (zeros with their border) count once, remaining empty cells once each
+    fn count_bbbv(&self, cleared_only: bool) -> u32 {
+        let mut visited = vec![false; self.total_fields as usize];
+        let mut bbbv = 0;
+        let discovered = |coords| matches!(self.cell_at(coords), CellState::DiscoveredEmpty(..));
+        
+        for coords in self.all_coords() {
+            if visited[self.linear_index(coords)] {
+                continue;
+            }
+            if self.cell_at(coords).empty_value() == Some(0) {
+                visited[self.linear_index(coords)] = true;
+                let mut cleared = discovered(coords);
+                let mut deque = VecDeque::from([coords]);
+                while let Some(current) = deque.pop_front() {
+                    for neighbor in self.neighbors(current) {
+                        let index = self.linear_index(neighbor);
+                        if visited[index] {
+                            continue;
+                        }
+                        if let Some(c) = self.cell_at(neighbor).empty_value() {
+                            visited[index] = true;
+                            cleared &= discovered(neighbor);
+                            if c == 0 {
+                                deque.push_back(neighbor);
+                            }
+                        }
+                    }
+                }
+                if cleared || !cleared_only {
+                    bbbv += 1;
+                }
+            }
+        }
+        for coords in self.all_coords() {
+            if !visited[self.linear_index(coords)] && self.cell_at(coords).empty_value().is_some()
+                    && (discovered(coords) || !cleared_only) {
+                bbbv += 1;
+            }
+        }
+        bbbv
+    }
+    
     pub fn new(sizes: [usize; DIMENSIONS_COUNT], wraps: [bool; DIMENSIONS_COUNT], mine_count: u32,
                initial: Option<[usize; DIMENSIONS_COUNT]>, seed: Option<u64>) -> Self {
         
@@ -374,7 +679,11 @@ impl GameBoard {
             total_fields:
                 size_x as u64 * size_y as u64 * size_z as u64
                 * size_u as u64 * size_v as u64 * size_w as u64,
+            bbbv: 0,
+            revision: 0,
         };
+        ret.bbbv = ret.count_bbbv(false);
+        ret.touch();
         
         // This also sets the state to failure if seed was used
         if let Some(init_coords) = initial {
@@ -384,3 +693,124 @@ impl GameBoard {
         ret
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bbbv() {
+        // Without mines the whole board is a single opening
+        let game = GameBoard::new([5, 4, 3, 1, 1, 1], [false; DIMENSIONS_COUNT], 0, None, Some(1));
+        assert_eq!(game.bbbv(), 1);
+        
+        // With a single free cell, it has to be clicked once
+        let game = GameBoard::new([3, 3, 1, 1, 1, 1], [false; DIMENSIONS_COUNT], 8, None, Some(1));
+        assert_eq!(game.bbbv(), 1);
+
+        // 3x1 board with the mine in the middle, both numbers have to be clicked
+        let mine = [1, 0, 0, 0, 0, 0];
+        let mut game = (0..).map(|seed| GameBoard::new([3, 1, 1, 1, 1, 1], [false; DIMENSIONS_COUNT], 1, None, Some(seed)))
+                            .find(|game| matches!(game.cell_at(mine), CellState::UndiscoveredMine(_))).unwrap();
+        assert_eq!(game.bbbv(), 2);
+        assert_eq!(game.bbbv_cleared(), 0);
+        game.probe_at([0, 0, 0, 0, 0, 0], false);
+        assert_eq!(game.bbbv_cleared(), 1);
+        game.probe_at(mine, false);
+        assert_eq!(game.bbbv_cleared(), 1);
+    }
+
+    #[test]
+    fn test_chord() {
+        // 3x1 board with the mine in the left corner
+        let mine = [0, 0, 0, 0, 0, 0];
+        let mut game = (0..).map(|seed| GameBoard::new([3, 1, 1, 1, 1, 1], [false; DIMENSIONS_COUNT], 1, None, Some(seed)))
+                            .find(|game| matches!(game.cell_at(mine), CellState::UndiscoveredMine(_))).unwrap();
+        let middle = [1, 0, 0, 0, 0, 0];
+        game.probe_at(middle, false);
+        // Nothing is marked yet, chording does nothing
+        assert!(!game.chord_explodes(middle));
+        assert_eq!(game.chord_at(middle), GameState::Running);
+        assert!(game.probe_explodes(mine, false));
+        // A misplaced flag makes the chord probe the mine
+        let other = [2, 0, 0, 0, 0, 0];
+        game.mark_at(other);
+        assert!(game.chord_explodes(middle));
+        assert!(!game.probe_explodes(other, true));
+        game.mark_at(other);
+        let revision = game.revision();
+        game.mark_at(mine);
+        assert_ne!(game.revision(), revision);
+        assert!(!game.probe_explodes(mine, false));
+        assert!(game.probe_explodes(mine, true));
+        assert!(!game.chord_explodes(middle));
+        assert_eq!(game.chord_at(middle), GameState::Victory);
+    }
+
+    #[test]
+    fn test_revision() {
+        let mut game = GameBoard::new([3, 3, 1, 1, 1, 1], [false; DIMENSIONS_COUNT], 1, None, Some(5));
+        let copy = game.clone();
+        game.highlight_at([0, 0, 0, 0, 0, 0], 1, false);
+        assert_ne!(game.revision(), copy.revision());
+        assert_eq!(game, copy);
+
+        // A loaded board never shares the revision of the saved one
+        let loaded: GameBoard = serde_json::from_str(&serde_json::to_string(&game).unwrap()).unwrap();
+        assert_eq!(loaded, game);
+        assert!(loaded.revision() > game.revision());
+    }
+
+    #[test]
+    fn test_find_next() {
+        // 3x2 board with the mine in the upper left corner, probing the opposite one leaves the cell below it
+        let mine = [0, 0, 0, 0, 0, 0];
+        let mut game = (0..).map(|seed| GameBoard::new([3, 2, 1, 1, 1, 1], [false; DIMENSIONS_COUNT], 1, None, Some(seed)))
+                            .find(|game| matches!(game.cell_at(mine), CellState::UndiscoveredMine(_))).unwrap();
+        game.probe_at([2, 1, 0, 0, 0, 0], false);
+        let numbers = [[1, 0, 0, 0, 0, 0], [1, 1, 0, 0, 0, 0]];
+        assert_eq!(game.find_next(CellQuery::OpenNumber, None), Some(numbers[0]));
+        assert_eq!(game.find_next(CellQuery::OpenNumber, Some(numbers[0])), Some(numbers[1]));
+        assert_eq!(game.find_next(CellQuery::OpenNumber, Some(numbers[1])), Some(numbers[0]));
+        assert_eq!(game.find_next(CellQuery::SafeCell, None), None);
+        game.mark_at(mine);
+        assert_eq!(game.find_next(CellQuery::OpenNumber, None), None);
+        assert_eq!(game.find_next(CellQuery::SafeCell, None), Some([0, 1, 0, 0, 0, 0]));
+    }
+
+    #[test]
+    fn test_highlight_groups() {
+        let mut game = GameBoard::new([4, 1, 1, 1, 1, 1], [false; DIMENSIONS_COUNT], 1, None, Some(1));
+        let group = |game: &GameBoard, group: usize| -> Vec<usize> {
+            game.all_coords().filter(|&c| game.cell_at(c).highlight_groups() & 1 << group != 0).map(|c| c[0]).collect()
+        };
+        game.highlight_undiscovered_neighbors([1, 0, 0, 0, 0, 0], 0);
+        game.highlight_undiscovered_neighbors([2, 0, 0, 0, 0, 0], 1);
+        assert_eq!(group(&game, 0), vec![0, 2]);
+        assert_eq!(group(&game, 1), vec![1, 3]);
+        game.highlight_at([2, 0, 0, 0, 0, 0], 1 << 1, true);
+        game.combine_highlight_groups(HighlightOperation::Union, 0, 1, 2);
+        assert_eq!(group(&game, 2), vec![0, 1, 2, 3]);
+        game.combine_highlight_groups(HighlightOperation::Intersection, 0, 1, 2);
+        assert_eq!(group(&game, 2), vec![2]);
+        // The target can be an operand
+        game.combine_highlight_groups(HighlightOperation::Difference, 1, 0, 1);
+        assert_eq!(group(&game, 1), vec![1, 3]);
+        game.clear_highlight_group(0);
+        assert_eq!(group(&game, 0), Vec::<usize>::new());
+        assert_eq!(group(&game, 2), vec![2]);
+    }
+
+    #[test]
+    fn test_neighbor_counts() {
+        let mut game = GameBoard::new([3, 3, 2, 1, 1, 1], [false; DIMENSIONS_COUNT], 1, None, Some(5));
+        let center = [1, 1, 0, 0, 0, 0];
+        game.mark_at([0, 0, 1, 0, 0, 0]);
+        let (total, by_axis) = game.neighbor_counts(center);
+        assert_eq!(total, NeighborCounts { undiscovered: 16, flagged: 1, revealed: 0 });
+        // 2 of each 3 columns (and rows) differ, in both layers of z except the center
+        assert_eq!(by_axis[0].undiscovered + by_axis[0].flagged, 12);
+        assert_eq!(by_axis[2], NeighborCounts { undiscovered: 8, flagged: 1, revealed: 0 });
+        assert_eq!(by_axis[3], NeighborCounts::default());
+    }
+}
@@ -0,0 +1,270 @@
+// Line based JSON protocol for external players, one request and one response per line:
+//
+//   > {"cmd": "new", "size": [9, 9, 1, 1, 1, 1], "mines": 10}
+//   < {"ok":true,"state":"Running","size":[9,9,1,1,1,1],"wrap":[false,false,false,false,false,false],"mines":10,...}
+//   > {"cmd": "probe", "coords": [4, 4, 0, 0, 0, 0]}
+//   < {"ok":true,"state":"Running","revealed":[{"coords":[4,4,0,0,0,0],"count":1}],...}
+//   > {"cmd": "mark", "coords": [3, 4, 0, 0, 0, 0]}
+//   > {"cmd": "chord", "coords": [4, 4, 0, 0, 0, 0]}
+//   > {"cmd": "state"}
+//   > {"cmd": "board"}
+//   > {"cmd": "quit"}
+//
+// Like in the GUI, the board is generated on the first probe (which is always safe) unless a seed is given.
+// Marking or chording before that is an error.
+// Only what the player could see is ever sent, mines are revealed once the game is over.
+use crate::minesweeper_model::{CellState, DIMENSIONS_COUNT, GameBoard, GameState, InitialGameSettings};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(tag = "cmd", rename_all = "snake_case")]
+pub enum Request {
+    New {
+        size: [usize; DIMENSIONS_COUNT],
+        #[serde(default)]
+        wrap: [bool; DIMENSIONS_COUNT],
+        mines: u32,
+        #[serde(default)]
+        seed: Option<String>, // hexadecimal, as in config.toml
+    },
+    Probe { coords: [usize; DIMENSIONS_COUNT] },
+    Mark { coords: [usize; DIMENSIONS_COUNT] },
+    Chord { coords: [usize; DIMENSIONS_COUNT] },
+    State,
+    Board,
+    Quit,
+}
+
+// Actions on a single cell
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Action {
+    Probe,
+    Mark,
+    Chord,
+}
+
+// Cell as the player sees it
+#[derive(Copy, Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VisibleCell {
+    Hidden,
+    Marked,
+    Revealed(u32),
+    Mine, // only after the game is over
+    Exploded,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct RevealedCell {
+    pub coords: [usize; DIMENSIONS_COUNT],
+    pub count: u32,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct Response {
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state: Option<GameState>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<[usize; DIMENSIONS_COUNT]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wrap: Option<[bool; DIMENSIONS_COUNT]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mines: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub marked: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<String>,
+    // Cells discovered by the last action
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revealed: Option<Vec<RevealedCell>>,
+    // Whole board, in the order of `GameBoard::all_coords` (x changes fastest)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cells: Option<Vec<VisibleCell>>,
+}
+
+impl Response {
+    fn error(message: impl Into<String>) -> Self {
+        Self { ok: false, error: Some(message.into()), ..Default::default() }
+    }
+}
+
+#[derive(Default)]
+pub struct Server {
+    settings: Option<InitialGameSettings>,
+    game: Option<GameBoard>,
+    quit: bool,
+}
+
+impl Server {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn should_quit(&self) -> bool {self.quit}
+
+    pub fn handle_line(&mut self, line: &str) -> Response {
+        match serde_json::from_str::<Request>(line) {
+            Ok(request) => self.handle(request),
+            Err(e) => Response::error(format!("Invalid request: {}", e)),
+        }
+    }
+
+    pub fn handle(&mut self, request: Request) -> Response {
+        match request {
+            Request::New { size, wrap, mines, seed } => {
+                if size.iter().any(|&s| s == 0 || s > 100) {
+                    return Response::error("Dimensions have to be between 1 and 100");
+                }
+                let total: usize = size.iter().product();
+                if mines == 0 || mines as usize >= total {
+                    return Response::error(format!("Number of mines has to be between 1 and {}", total - 1));
+                }
+                if seed.as_ref().is_some_and(|s| u64::from_str_radix(s, 16).is_err()) {
+                    return Response::error("Seed has to be a hexadecimal number");
+                }
+                self.settings = Some(InitialGameSettings { name: "Bot".into(), size, wrap, mines, seed });
+                self.game = None;
+                self.status()
+            },
+            Request::Probe { coords } => self.act(Action::Probe, coords),
+            Request::Mark { coords } => self.act(Action::Mark, coords),
+            Request::Chord { coords } => self.act(Action::Chord, coords),
+            Request::State => {
+                if self.settings.is_none() {
+                    return Response::error("No game, send `new` first");
+                }
+                self.status()
+            },
+            Request::Board => {
+                let Some(settings) = &self.settings else {
+                    return Response::error("No game, send `new` first");
+                };
+                let cells = match &self.game {
+                    Some(game) => game.all_coords().map(|c| visible_cell(game, c)).collect(),
+                    None => vec![VisibleCell::Hidden; settings.size.iter().product()],
+                };
+                Response { cells: Some(cells), ..self.status() }
+            },
+            Request::Quit => {
+                self.quit = true;
+                Response { ok: true, ..Default::default() }
+            },
+        }
+    }
+
+    // Apply a player action and report cells it revealed
+    fn act(&mut self, action: Action, coords: [usize; DIMENSIONS_COUNT]) -> Response {
+        let Some(settings) = &self.settings else {
+            return Response::error("No game, send `new` first");
+        };
+        if (0..DIMENSIONS_COUNT).any(|i| coords[i] >= settings.size[i]) {
+            return Response::error("Coordinates are outside the board");
+        }
+
+        let Some(game) = &mut self.game else {
+            // Only a probe starts the game
+            if action != Action::Probe {
+                return Response::error("Game not started, send `probe` first");
+            }
+            let game = if let Some(seed) = &settings.seed {
+                let mut game = GameBoard::new(settings.size, settings.wrap, settings.mines, None,
+                                              u64::from_str_radix(seed, 16).ok());
+                game.probe_at(coords, true);
+                game
+            } else {
+                GameBoard::new(settings.size, settings.wrap, settings.mines, Some(coords), None)
+            };
+            let revealed = revealed_cells(&game, |_| true);
+            self.game = Some(game);
+            return Response { revealed: Some(revealed), ..self.status() };
+        };
+        if game.state() != GameState::Running {
+            return Response::error("Game is over, send `new` to start another");
+        }
+
+        let before: Vec<_> = game.all_coords().map(|c| visible_cell(game, c)).collect();
+        match action {
+            Action::Probe => {game.probe_at(coords, false);},
+            Action::Mark => game.mark_at(coords),
+            Action::Chord => {game.chord_at(coords);},
+        }
+        let revealed = revealed_cells(game, |c| !matches!(before[c], VisibleCell::Revealed(_)));
+        Response { revealed: Some(revealed), ..self.status() }
+    }
+
+    fn status(&self) -> Response {
+        let Some(settings) = &self.settings else {
+            return Response { ok: true, ..Default::default() };
+        };
+        Response {
+            ok: true,
+            state: Some(self.game.as_ref().map_or(GameState::Running, |g| g.state())),
+            size: Some(settings.size),
+            wrap: Some(settings.wrap),
+            mines: Some(settings.mines),
+            marked: Some(self.game.as_ref().map_or(0, |g| g.marked_as_mine())),
+            seed: self.game.as_ref().map(|g| format!("{:x}", g.seed())),
+            ..Default::default()
+        }
+    }
+}
+
+// Discovered cells, `include` gets index of the cell in `all_coords` order
+fn revealed_cells(game: &GameBoard, include: impl Fn(usize) -> bool) -> Vec<RevealedCell> {
+    game.all_coords().enumerate().filter_map(|(ii, c)| match game.cell_at(c) {
+        CellState::DiscoveredEmpty(count, _, _) if include(ii) => Some(RevealedCell { coords: c, count }),
+        _ => None
+    }).collect()
+}
+
+fn visible_cell(game: &GameBoard, coords: [usize; DIMENSIONS_COUNT]) -> VisibleCell {
+    let finished = game.state() != GameState::Running;
+    match game.cell_at(coords) {
+        CellState::UndiscoveredMine(_) if finished => VisibleCell::Mine,
+        CellState::UndiscoveredMine(_) | CellState::UndiscoveredEmpty(..) => VisibleCell::Hidden,
+        CellState::MarkedMine(_) | CellState::MarkedEmpty(..) => VisibleCell::Marked,
+        CellState::ExplodedMine(_) => VisibleCell::Exploded,
+        CellState::DiscoveredEmpty(count, _, _) => VisibleCell::Revealed(count),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session() {
+        let mut server = Server::new();
+        assert!(!server.handle_line(r#"{"cmd": "probe", "coords": [0, 0, 0, 0, 0, 0]}"#).ok);
+        assert!(!server.handle_line("nonsense").ok);
+
+        let response = server.handle_line(r#"{"cmd": "new", "size": [9, 9, 1, 1, 1, 1], "mines": 10}"#);
+        assert!(response.ok);
+        assert_eq!(response.seed, None);
+        assert!(!server.handle_line(r#"{"cmd": "mark", "coords": [0, 0, 0, 0, 0, 0]}"#).ok);
+
+        // First probe is always safe and reveals at least the probed cell
+        let response = server.handle_line(r#"{"cmd": "probe", "coords": [4, 4, 0, 0, 0, 0]}"#);
+        assert!(response.ok);
+        assert_ne!(response.state, Some(GameState::Loss));
+        assert!(response.revealed.unwrap().iter().any(|r| r.coords == [4, 4, 0, 0, 0, 0]));
+        assert!(response.seed.is_some());
+
+        // Probing a revealed cell reveals nothing new
+        let response = server.handle_line(r#"{"cmd": "probe", "coords": [4, 4, 0, 0, 0, 0]}"#);
+        assert_eq!(response.revealed, Some(vec![]));
+
+        let response = server.handle_line(r#"{"cmd": "board"}"#);
+        let cells = response.cells.unwrap();
+        assert_eq!(cells.len(), 81);
+        assert!(!cells.contains(&VisibleCell::Mine));
+        assert!(matches!(cells[4 * 9 + 4], VisibleCell::Revealed(_)));
+
+        assert!(!server.handle_line(r#"{"cmd": "mark", "coords": [9, 0, 0, 0, 0, 0]}"#).ok);
+        server.handle_line(r#"{"cmd": "quit"}"#);
+        assert!(server.should_quit());
+    }
+}
@@ -0,0 +1,547 @@
+use crate::minesweeper_model::{CellState, DIMENSIONS_COUNT, GameBoard, GameState};
+use std::collections::{HashMap, hash_map::Entry};
+
+// Components with more unknown cells than this are not searched exhaustively
+const MAX_COMPONENT_CELLS: usize = 48;
+// Upper bound on the number of search nodes visited per component
+const MAX_SEARCH_STEPS: u64 = 2_000_000;
+
+// A revealed number together with the unknown cells around it:
+//   sum of (multiplicity * is_mine) over `cells` must equal `mines`
+struct Constraint {
+    cells: Vec<(usize, i64)>,
+    mines: i64,
+}
+
+// Result of searching one connected group of frontier cells
+struct Component {
+    cells: Vec<usize>,
+    // Solutions with given amount of mines (scaled so the largest entry is 1)
+    solutions: Vec<f64>,
+    // Per cell (indexed like `cells`) solutions in which the cell holds a mine
+    cell_solutions: Vec<Vec<f64>>,
+}
+
+// What can be deduced about the board from the revealed numbers and the total mine count alone.
+// Flags placed by the player are not trusted, they are treated as undiscovered cells.
+pub struct BoardAnalysis {
+    safe: Vec<[usize; DIMENSIONS_COUNT]>,
+    mines: Vec<[usize; DIMENSIONS_COUNT]>,
+    probabilities: HashMap<[usize; DIMENSIONS_COUNT], f64>,
+    exact: bool,
+}
+
+impl BoardAnalysis {
+    // Getters
+    pub fn safe_cells(&self) -> &[[usize; DIMENSIONS_COUNT]] {&self.safe}
+    pub fn mine_cells(&self) -> &[[usize; DIMENSIONS_COUNT]] {&self.mines}
+    pub fn is_exact(&self) -> bool {self.exact}
+
+    // Probability of given unknown cell containing a mine (None for revealed cells)
+    pub fn mine_probability(&self, coordinates: [usize; DIMENSIONS_COUNT]) -> Option<f64> {
+        self.probabilities.get(&coordinates).copied()
+    }
+
+    // Analyse the board as the player sees it
+    pub fn new(game: &GameBoard) -> Self {
+        let coords: Vec<_> = game.all_coords().collect();
+        let revealed: Vec<Option<i64>> = coords.iter().map(|&c| {
+            match game.cell_at(c) {
+                CellState::DiscoveredEmpty(count, _, _) => Some(count as i64),
+                _ => None
+            }
+        }).collect();
+
+        // Build constraints from revealed numbers
+        let mut constraints = Vec::new();
+        for (ii, value) in revealed.iter().enumerate() {
+            if let Some(count) = value {
+                let mut cells: Vec<(usize, i64)> = Vec::new();
+                for neighbor in game.neighbors(coords[ii]) {
+                    let ni = game.linear_index(neighbor);
+                    if revealed[ni].is_none() {
+                        if let Some(entry) = cells.iter_mut().find(|(c, _)| *c == ni) {
+                            entry.1 += 1;
+                        } else {
+                            cells.push((ni, 1));
+                        }
+                    }
+                }
+                if !cells.is_empty() {
+                    constraints.push(Constraint { cells, mines: *count });
+                }
+            }
+        }
+
+        // Simple deductions first, they shrink the search considerably
+        let mut known: HashMap<usize, bool> = HashMap::new();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for constraint in &constraints {
+                let mut remaining = constraint.mines;
+                let mut open_weight = 0;
+                for &(cell, mult) in &constraint.cells {
+                    match known.get(&cell) {
+                        Some(true) => remaining -= mult,
+                        Some(false) => {},
+                        None => open_weight += mult,
+                    }
+                }
+                if open_weight == 0 {
+                    continue;
+                }
+                if remaining == 0 || remaining == open_weight {
+                    for &(cell, _) in &constraint.cells {
+                        if let Entry::Vacant(entry) = known.entry(cell) {
+                            entry.insert(remaining != 0);
+                            changed = true;
+                        }
+                    }
+                }
+            }
+        }
+
+        // Reduce constraints to the cells that are still open
+        let reduced: Vec<Constraint> = constraints.iter().filter_map(|constraint| {
+            let mut mines = constraint.mines;
+            let mut cells = Vec::new();
+            for &(cell, mult) in &constraint.cells {
+                match known.get(&cell) {
+                    Some(true) => mines -= mult,
+                    Some(false) => {},
+                    None => cells.push((cell, mult)),
+                }
+            }
+            if cells.is_empty() {None} else {Some(Constraint { cells, mines })}
+        }).collect();
+
+        // Split open frontier into independent components
+        let mut parent: HashMap<usize, usize> = HashMap::new();
+        fn find(parent: &mut HashMap<usize, usize>, cell: usize) -> usize {
+            let p = *parent.entry(cell).or_insert(cell);
+            if p == cell {
+                cell
+            } else {
+                let root = find(parent, p);
+                parent.insert(cell, root);
+                root
+            }
+        }
+        for constraint in &reduced {
+            let first = find(&mut parent, constraint.cells[0].0);
+            for &(cell, _) in &constraint.cells[1..] {
+                let root = find(&mut parent, cell);
+                if root != first {
+                    parent.insert(root, first);
+                }
+            }
+        }
+        let mut groups: HashMap<usize, Vec<usize>> = HashMap::new();
+        let mut frontier: Vec<usize> = parent.keys().copied().collect();
+        frontier.sort();
+        for cell in frontier {
+            let root = find(&mut parent, cell);
+            groups.entry(root).or_default().push(cell);
+        }
+        let mut roots: Vec<usize> = groups.keys().copied().collect();
+        roots.sort();
+
+        let mut exact = true;
+        let mut components = Vec::new();
+        for root in roots {
+            let cells = groups.remove(&root).unwrap();
+            let local: Vec<&Constraint> = reduced.iter()
+                .filter(|c| find(&mut parent, c.cells[0].0) == root).collect();
+            match search_component(cells.clone(), &local) {
+                Some(component) => components.push(component),
+                None => exact = false,
+            }
+        }
+
+        // Combine components with the cells nobody knows anything about
+        let unknown_total = revealed.iter().filter(|v| v.is_none()).count();
+        let known_mines = known.values().filter(|&&m| m).count() as i64;
+        let searched: usize = components.iter().map(|c| c.cells.len()).sum();
+        let interior = unknown_total - known.len() - searched;
+        let mines_left = game.mines_present() as i64 - known_mines;
+
+        let weights = interior_weights(interior, mines_left, searched);
+        let totals: Vec<Vec<f64>> = components.iter().map(|c| c.solutions.clone()).collect();
+        let everything = convolve_all(&totals);
+        let grand_total: f64 = everything.iter().enumerate().map(|(k, v)| v * weights[k]).sum();
+
+        let mut probabilities = HashMap::new();
+        let mut safe = Vec::new();
+        let mut mines = Vec::new();
+        for (&cell, &is_mine) in &known {
+            probabilities.insert(coords[cell], if is_mine {1.0} else {0.0});
+            if is_mine {mines.push(coords[cell]);} else {safe.push(coords[cell]);}
+        }
+
+        if grand_total > 0.0 {
+            for (ci, component) in components.iter().enumerate() {
+                let others: Vec<Vec<f64>> = totals.iter().enumerate()
+                    .filter(|(oi, _)| *oi != ci).map(|(_, t)| t.clone()).collect();
+                let others = convolve_all(&others);
+                // Weight of the rest of the board given this component holds k mines
+                let rest: Vec<f64> = (0..component.solutions.len()).map(|k| {
+                    others.iter().enumerate().map(|(ko, v)| v * weights.get(k + ko).copied().unwrap_or(0.0)).sum()
+                }).collect();
+                let denominator: f64 = component.solutions.iter().zip(&rest).map(|(s, r)| s * r).sum();
+                for (vi, &cell) in component.cells.iter().enumerate() {
+                    let numerator: f64 = component.cell_solutions[vi].iter().zip(&rest).map(|(s, r)| s * r).sum();
+                    let p = if denominator > 0.0 {numerator / denominator} else {0.0};
+                    probabilities.insert(coords[cell], p);
+                    // Without exact weights only placements impossible in every solution count
+                    let never_mine = component.cell_solutions[vi].iter().zip(&rest)
+                        .all(|(s, r)| *s == 0.0 || (exact && *r == 0.0));
+                    let always_mine = component.cell_solutions[vi].iter().zip(&component.solutions).zip(&rest)
+                        .all(|((s, t), r)| s == t || (exact && *r == 0.0));
+                    if never_mine {
+                        safe.push(coords[cell]);
+                    } else if always_mine {
+                        mines.push(coords[cell]);
+                    }
+                }
+            }
+        } else {
+            exact = false;
+        }
+
+        // Everything else shares the expected density of the mines left for it
+        let interior_probability = if interior == 0 {
+            0.0
+        } else if grand_total > 0.0 {
+            everything.iter().enumerate()
+                .map(|(k, v)| v * weights[k] * (mines_left - k as i64) as f64).sum::<f64>()
+                / grand_total / interior as f64
+        } else {
+            (mines_left as f64 / (unknown_total - known.len()).max(1) as f64).clamp(0.0, 1.0)
+        };
+        for (ii, value) in revealed.iter().enumerate() {
+            if value.is_none() && !probabilities.contains_key(&coords[ii]) {
+                probabilities.insert(coords[ii], interior_probability);
+                if exact && interior_probability == 0.0 {
+                    safe.push(coords[ii]);
+                } else if exact && interior_probability == 1.0 {
+                    mines.push(coords[ii]);
+                }
+            }
+        }
+
+        safe.sort_by_key(|&c| game.linear_index(c));
+        mines.sort_by_key(|&c| game.linear_index(c));
+        Self { safe, mines, probabilities, exact }
+    }
+}
+
+// Enumerate all mine placements of one component, None if it is too large to search
+fn search_component(cells: Vec<usize>, constraints: &[&Constraint]) -> Option<Component> {
+    if cells.len() > MAX_COMPONENT_CELLS {
+        return None;
+    }
+    let index: HashMap<usize, usize> = cells.iter().enumerate().map(|(ii, &c)| (c, ii)).collect();
+
+    // Order cells so that constraints get closed as early as possible
+    let mut order: Vec<usize> = Vec::new();
+    let mut placed = vec![false; cells.len()];
+    for constraint in constraints {
+        for &(cell, _) in &constraint.cells {
+            let ci = index[&cell];
+            if !placed[ci] {
+                placed[ci] = true;
+                order.push(ci);
+            }
+        }
+    }
+
+    // For every cell, the constraints it takes part in with its multiplicity
+    let mut memberships: Vec<Vec<(usize, i64)>> = vec![Vec::new(); cells.len()];
+    for (ki, constraint) in constraints.iter().enumerate() {
+        for &(cell, mult) in &constraint.cells {
+            memberships[index[&cell]].push((ki, mult));
+        }
+    }
+
+    let mut state = SearchState {
+        remaining: constraints.iter().map(|c| c.mines).collect(),
+        open_weight: constraints.iter().map(|c| c.cells.iter().map(|(_, m)| m).sum()).collect(),
+        assignment: vec![false; cells.len()],
+        solutions: vec![0.0; cells.len() + 1],
+        cell_solutions: vec![vec![0.0; cells.len() + 1]; cells.len()],
+        steps: 0,
+    };
+    if !state.search(&order, 0, 0, &memberships) {
+        return None;
+    }
+
+    let scale = state.solutions.iter().cloned().fold(0.0, f64::max);
+    if scale == 0.0 {
+        // Revealed numbers contradict each other, nothing sensible can be said
+        return None;
+    }
+    Some(Component {
+        cells,
+        solutions: state.solutions.iter().map(|v| v / scale).collect(),
+        cell_solutions: state.cell_solutions.iter().map(|row| row.iter().map(|v| v / scale).collect()).collect(),
+    })
+}
+
+struct SearchState {
+    remaining: Vec<i64>,
+    open_weight: Vec<i64>,
+    assignment: Vec<bool>,
+    solutions: Vec<f64>,
+    cell_solutions: Vec<Vec<f64>>,
+    steps: u64,
+}
+
+impl SearchState {
+    // Returns false when the step budget ran out
+    fn search(&mut self, order: &[usize], depth: usize, mines: usize, memberships: &[Vec<(usize, i64)>]) -> bool {
+        self.steps += 1;
+        if self.steps > MAX_SEARCH_STEPS {
+            return false;
+        }
+        if depth == order.len() {
+            self.solutions[mines] += 1.0;
+            for (ci, &is_mine) in self.assignment.iter().enumerate() {
+                if is_mine {
+                    self.cell_solutions[ci][mines] += 1.0;
+                }
+            }
+            return true;
+        }
+
+        let cell = order[depth];
+        for value in [false, true] {
+            let mut feasible = true;
+            for &(ki, mult) in &memberships[cell] {
+                self.open_weight[ki] -= mult;
+                if value {
+                    self.remaining[ki] -= mult;
+                }
+                if self.remaining[ki] < 0 || self.remaining[ki] > self.open_weight[ki] {
+                    feasible = false;
+                }
+            }
+            self.assignment[cell] = value;
+            let finished = !feasible || self.search(order, depth + 1, mines + value as usize, memberships);
+            for &(ki, mult) in &memberships[cell] {
+                self.open_weight[ki] += mult;
+                if value {
+                    self.remaining[ki] += mult;
+                }
+            }
+            self.assignment[cell] = false;
+            if !finished {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+// Relative number of ways to place the leftover mines into `interior` cells,
+// indexed by how many mines the searched frontier holds (largest entry is 1)
+fn interior_weights(interior: usize, mines_left: i64, frontier: usize) -> Vec<f64> {
+    let mut log_weights = vec![f64::NEG_INFINITY; frontier + 1];
+    // ln C(n, j) - ln C(n, j-1) = ln((n - j + 1) / j)
+    let mut log_choose = 0.0;
+    let mut previous_j = 0i64;
+    for k in (0..=frontier).rev() {
+        let j = mines_left - k as i64;
+        if j < 0 || j > interior as i64 {
+            continue;
+        }
+        while previous_j < j {
+            previous_j += 1;
+            log_choose += ((interior as i64 - previous_j + 1) as f64 / previous_j as f64).ln();
+        }
+        log_weights[k] = log_choose;
+    }
+    let max = log_weights.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    log_weights.iter().map(|w| if max.is_finite() {(w - max).exp()} else {0.0}).collect()
+}
+
+// Distribution of the total mine count over several independent components
+fn convolve_all(distributions: &[Vec<f64>]) -> Vec<f64> {
+    let mut ret = vec![1.0];
+    for distribution in distributions {
+        let mut next = vec![0.0; ret.len() + distribution.len() - 1];
+        for (ii, a) in ret.iter().enumerate() {
+            for (jj, b) in distribution.iter().enumerate() {
+                next[ii + jj] += a * b;
+            }
+        }
+        let scale = next.iter().cloned().fold(0.0, f64::max);
+        ret = if scale > 0.0 {next.iter().map(|v| v / scale).collect()} else {next};
+    }
+    ret
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ProbeVerdict {
+    // No cell could have been proven safe, some risk had to be taken
+    ForcedGuess,
+    // The probed cell was unproven, but other cells were provably safe
+    AvoidableGuess,
+    // The probed cell was provably a mine
+    DeducibleMistake,
+}
+
+// Summary of a lost game
+pub struct LossAnalysis {
+    pub fatal_cell: Option<[usize; DIMENSIONS_COUNT]>,
+    pub verdict: ProbeVerdict,
+    pub fatal_probability: Option<f64>,
+    pub probability_exact: bool,
+    pub wrong_flags: Vec<[usize; DIMENSIONS_COUNT]>,
+    pub missed_mines: Vec<[usize; DIMENSIONS_COUNT]>,
+}
+
+impl LossAnalysis {
+    // `before` is the board just before the losing action (a probe or a chord), `after` the lost one.
+    // The analysis looks at `before`, so cells revealed by the same chord don't count as known.
+    pub fn new(before: &GameBoard, after: &GameBoard) -> Option<Self> {
+        if after.state() != GameState::Loss {
+            return None;
+        }
+        let mut fatal_cell = None;
+        let mut wrong_flags = Vec::new();
+        let mut missed_mines = Vec::new();
+        for coords in after.all_coords() {
+            match after.cell_at(coords) {
+                // A chord stops at the first mine, so only one cell explodes per action
+                CellState::ExplodedMine(_) if !matches!(before.cell_at(coords), CellState::ExplodedMine(_)) => {
+                    fatal_cell = fatal_cell.or(Some(coords));
+                },
+                CellState::MarkedEmpty(..) => wrong_flags.push(coords),
+                CellState::UndiscoveredMine(_) => missed_mines.push(coords),
+                _ => {}
+            }
+        }
+
+        let analysis = BoardAnalysis::new(before);
+        let fatal_probability = fatal_cell.and_then(|c| analysis.mine_probability(c));
+        let verdict = if fatal_cell.is_some_and(|c| analysis.mine_cells().contains(&c)) {
+            ProbeVerdict::DeducibleMistake
+        } else if !analysis.safe_cells().is_empty() {
+            ProbeVerdict::AvoidableGuess
+        } else {
+            ProbeVerdict::ForcedGuess
+        };
+
+        Some(Self {
+            fatal_cell,
+            verdict,
+            fatal_probability,
+            probability_exact: analysis.is_exact(),
+            wrong_flags,
+            missed_mines,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interior_weights() {
+        // 4 interior cells, 2 mines left, frontier of 2 cells: C(4,2), C(4,1), C(4,0) = 6, 4, 1
+        let weights = interior_weights(4, 2, 2);
+        assert!((weights[0] - 1.0).abs() < 1e-9);
+        assert!((weights[1] - 4.0 / 6.0).abs() < 1e-9);
+        assert!((weights[2] - 1.0 / 6.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_fully_revealed_frontier() {
+        // Seeded 2D board, probe everything that is safe and check the analysis agrees
+        let mut game = GameBoard::new([9, 9, 1, 1, 1, 1], [false; DIMENSIONS_COUNT], 10, None, Some(7));
+        let empties: Vec<_> = game.all_coords()
+            .filter(|&c| matches!(game.cell_at(c), CellState::UndiscoveredEmpty(..))).collect();
+        for coords in empties {
+            game.probe_at(coords, false);
+        }
+        assert_eq!(game.state(), GameState::Victory);
+        let analysis = BoardAnalysis::new(&game);
+        assert_eq!(analysis.mine_cells().len(), 10);
+        assert!(analysis.safe_cells().is_empty());
+        assert!(analysis.is_exact());
+    }
+
+    #[test]
+    fn test_single_unknown() {
+        // Reveal everything but one empty cell, it must be deduced as safe.
+        // The kept cell has no empty neighbors with a zero count, so no cascade reveals it.
+        let mut game = GameBoard::new([9, 9, 1, 1, 1, 1], [false; DIMENSIONS_COUNT], 10, None, Some(11));
+        let empties: Vec<_> = game.all_coords()
+            .filter(|&c| matches!(game.cell_at(c), CellState::UndiscoveredEmpty(..))).collect();
+        let last = *empties.iter().find(|&&c| {
+            game.neighbors(c).into_iter().all(|n| !matches!(game.cell_at(n), CellState::UndiscoveredEmpty(0, ..)))
+        }).unwrap();
+        for &coords in empties.iter().filter(|&&c| c != last) {
+            game.probe_at(coords, false);
+        }
+        assert_eq!(game.state(), GameState::Running);
+        let analysis = BoardAnalysis::new(&game);
+        assert_eq!(analysis.mine_probability(last), Some(0.0));
+        assert!(analysis.safe_cells().contains(&last));
+    }
+
+    // Seeded 1-mine board with the mine at `mine`
+    fn board_with_mine(sizes: [usize; DIMENSIONS_COUNT], mine: [usize; DIMENSIONS_COUNT]) -> GameBoard {
+        (0..).map(|seed| GameBoard::new(sizes, [false; DIMENSIONS_COUNT], 1, None, Some(seed)))
+             .find(|game| matches!(game.cell_at(mine), CellState::UndiscoveredMine(_))).unwrap()
+    }
+
+    // Probes `coords` and analyses the loss
+    fn lose_at(game: &mut GameBoard, coords: [usize; DIMENSIONS_COUNT]) -> LossAnalysis {
+        let before = game.clone();
+        assert_eq!(game.probe_at(coords, false), GameState::Loss);
+        LossAnalysis::new(&before, game).unwrap()
+    }
+
+    #[test]
+    fn test_loss_deducible_mistake() {
+        // 3x1 board, probing the far corner leaves only the mine undiscovered
+        let mine = [0, 0, 0, 0, 0, 0];
+        let mut game = board_with_mine([3, 1, 1, 1, 1, 1], mine);
+        game.probe_at([2, 0, 0, 0, 0, 0], false);
+        assert!(LossAnalysis::new(&game.clone(), &game).is_none());
+        let loss = lose_at(&mut game, mine);
+        assert_eq!(loss.fatal_cell, Some(mine));
+        assert_eq!(loss.verdict, ProbeVerdict::DeducibleMistake);
+        assert_eq!(loss.fatal_probability, Some(1.0));
+        assert!(loss.wrong_flags.is_empty());
+        assert!(loss.missed_mines.is_empty());
+    }
+
+    #[test]
+    fn test_loss_avoidable_guess() {
+        // 4x1 board, the 1 next to the mine can't tell it apart from its other neighbor,
+        // but the mine count proves the far corner safe
+        let mine = [0, 0, 0, 0, 0, 0];
+        let mut game = board_with_mine([4, 1, 1, 1, 1, 1], mine);
+        game.probe_at([1, 0, 0, 0, 0, 0], false);
+        game.mark_at([3, 0, 0, 0, 0, 0]);
+        let loss = lose_at(&mut game, mine);
+        assert_eq!(loss.verdict, ProbeVerdict::AvoidableGuess);
+        assert_eq!(loss.fatal_probability, Some(0.5));
+        assert_eq!(loss.wrong_flags, vec![[3, 0, 0, 0, 0, 0]]);
+    }
+
+    #[test]
+    fn test_loss_forced_guess() {
+        // Nothing is discovered on a fresh board, any probe is a guess
+        let mine = [1, 0, 0, 0, 0, 0];
+        let mut game = board_with_mine([3, 3, 1, 1, 1, 1], mine);
+        let loss = lose_at(&mut game, mine);
+        assert_eq!(loss.fatal_cell, Some(mine));
+        assert_eq!(loss.verdict, ProbeVerdict::ForcedGuess);
+        assert!(loss.missed_mines.is_empty());
+    }
+}
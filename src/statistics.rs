@@ -0,0 +1,153 @@
+use crate::minesweeper_model::DIMENSIONS_COUNT;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+// How many winning runs are kept per preset
+pub const HIGH_SCORE_ENTRIES: usize = 10;
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ReplayActionKind {
+    Probe,
+    ProbeMarked, // probe with probing of marked cells allowed
+    Mark,
+    Chord,
+}
+
+// Single player action, time is measured from the first click
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ReplayAction {
+    pub time_ms: u64,
+    pub kind: ReplayActionKind,
+    pub coords: [usize; DIMENSIONS_COUNT],
+}
+
+// Winning run, holds everything needed to recreate the board (and the game, if replay is present)
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HighScore {
+    pub date: u64, // seconds since unix epoch
+    pub time_ms: u64,
+    pub size: [usize; DIMENSIONS_COUNT],
+    pub wrap: [bool; DIMENSIONS_COUNT],
+    pub mines: u32,
+    pub seed: u64,
+    pub bbbv: u32,
+    pub assists: bool, // hints (find next) were used
+    pub replay: Vec<ReplayAction>,
+}
+
+// Aggregated results of all finished games played with one preset (or one custom setting)
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PresetStatistics {
+    pub played: u32,
+    pub won: u32,
+    pub current_streak: u32,
+    pub best_streak: u32,
+    pub best_time_ms: Option<u64>,
+    pub total_win_time_ms: u64,
+    pub best_bbbv_per_second: Option<f64>,
+}
+
+impl PresetStatistics {
+    pub fn win_rate(&self) -> f64 {
+        if self.played == 0 {0.0} else {self.won as f64 / self.played as f64}
+    }
+
+    pub fn average_time_ms(&self) -> Option<u64> {
+        if self.won == 0 {None} else {Some(self.total_win_time_ms / self.won as u64)}
+    }
+
+    pub fn record_win(&mut self, time: Duration, bbbv: u32) {
+        let time_ms = time.as_millis() as u64;
+        self.played += 1;
+        self.won += 1;
+        self.current_streak += 1;
+        self.best_streak = self.best_streak.max(self.current_streak);
+        self.best_time_ms = Some(self.best_time_ms.map_or(time_ms, |t| t.min(time_ms)));
+        self.total_win_time_ms += time_ms;
+
+        let bbbv_per_second = bbbv as f64 / time.as_secs_f64().max(0.001);
+        self.best_bbbv_per_second = Some(self.best_bbbv_per_second.map_or(bbbv_per_second,
+                                                                          |b| b.max(bbbv_per_second)));
+    }
+
+    pub fn record_loss(&mut self) {
+        self.played += 1;
+        self.current_streak = 0;
+    }
+}
+
+// Statistics of all presets, keyed by preset name (or description of custom settings)
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Statistics {
+    pub presets: BTreeMap<String, PresetStatistics>,
+    pub high_scores: BTreeMap<String, Vec<HighScore>>,
+}
+
+impl Statistics {
+    // Key under which the statistics are kept in the app storage
+    pub const STORAGE_KEY: &'static str = "statistics";
+
+    pub fn record(&mut self, key: &str, victory: bool, time: Duration, bbbv: u32) {
+        let entry = self.presets.entry(key.into()).or_default();
+        if victory {
+            entry.record_win(time, bbbv);
+        } else {
+            entry.record_loss();
+        }
+    }
+
+    // Returns position in the table (from 0), None if the run was not fast enough
+    pub fn record_high_score(&mut self, key: &str, high_score: HighScore) -> Option<usize> {
+        let table = self.high_scores.entry(key.into()).or_default();
+        let position = table.iter().position(|h| h.time_ms > high_score.time_ms).unwrap_or(table.len());
+        if position >= HIGH_SCORE_ENTRIES {
+            return None;
+        }
+        table.insert(position, high_score);
+        table.truncate(HIGH_SCORE_ENTRIES);
+        Some(position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_streaks_and_times() {
+        let mut stats = Statistics::default();
+        stats.record("a", true, Duration::from_secs(20), 10);
+        stats.record("a", true, Duration::from_secs(10), 10);
+        stats.record("a", false, Duration::from_secs(5), 10);
+        stats.record("a", true, Duration::from_secs(30), 10);
+
+        let a = &stats.presets["a"];
+        assert_eq!(a.played, 4);
+        assert_eq!(a.won, 3);
+        assert_eq!(a.current_streak, 1);
+        assert_eq!(a.best_streak, 2);
+        assert_eq!(a.best_time_ms, Some(10_000));
+        assert_eq!(a.average_time_ms(), Some(20_000));
+        assert_eq!(a.best_bbbv_per_second, Some(1.0));
+        assert_eq!(a.win_rate(), 0.75);
+    }
+
+    #[test]
+    fn test_high_score_table() {
+        let mut stats = Statistics::default();
+        let run = |time_ms| HighScore {
+            date: 0, time_ms, size: [9, 9, 1, 1, 1, 1], wrap: [false; DIMENSIONS_COUNT],
+            mines: 10, seed: 0, bbbv: 10, assists: false, replay: vec![]
+        };
+        for ii in 0..HIGH_SCORE_ENTRIES as u64 {
+            assert_eq!(stats.record_high_score("a", run(1000 + ii)), Some(ii as usize));
+        }
+        assert_eq!(stats.record_high_score("a", run(5000)), None);
+        assert_eq!(stats.record_high_score("a", run(500)), Some(0));
+        assert_eq!(stats.high_scores["a"].len(), HIGH_SCORE_ENTRIES);
+        assert_eq!(stats.high_scores["a"].last().unwrap().time_ms, 1000 + HIGH_SCORE_ENTRIES as u64 - 2);
+    }
+}